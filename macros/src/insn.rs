@@ -18,6 +18,12 @@ macro_rules! insn_format {
              fn rs2(&self,code:&TerminusInsnT)->TerminusInsnT {
                 (*code >> 20) & 0x1f
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "rs2"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, _imm:TerminusInsnT)->TerminusInsnT {
+                (rd & 0x1f) << 7 | (rs1 & 0x1f) << 15 | (rs2 & 0x1f) << 20
+             }
         }
     };
     ($name:ident, I) => {
@@ -37,6 +43,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 12
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rd & 0x1f) << 7 | (rs1 & 0x1f) << 15 | (imm & 0xfff) << 20
+             }
         }
     };
     ($name:ident, S) => {
@@ -57,6 +69,12 @@ macro_rules! insn_format {
             fn imm_len(&self)-> usize {
                 12
             }
+             fn fields(&self)->&'static [&'static str] {
+                &["rs1", "rs2", "imm"]
+             }
+             fn encode(&self,_rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rs1 & 0x1f) << 15 | (rs2 & 0x1f) << 20 | (imm & 0x1f) << 7 | ((imm >> 5) & 0x7f) << 25
+             }
 
 
         }
@@ -78,6 +96,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 13
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rs1", "rs2", "imm"]
+             }
+             fn encode(&self,_rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rs1 & 0x1f) << 15 | (rs2 & 0x1f) << 20 | ((imm >> 12) & 0x1) << 31 | ((imm >> 11) & 0x1) << 7 | ((imm >> 5) & 0x3f) << 25 | ((imm >> 1) & 0xf) << 8
+             }
         }
     };
     ($name:ident, U) => {
@@ -94,6 +118,15 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 32
              }
+             fn imm_shift(&self)-> usize {
+                12
+             }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, _rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rd & 0x1f) << 7 | (imm & 0xfffff000)
+             }
 
 
         }
@@ -113,6 +146,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 21
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, _rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rd & 0x1f) << 7 | ((imm >> 20) & 0x1) << 31 | ((imm >> 12) & 0xff) << 12 | ((imm >> 11) & 0x1) << 20 | ((imm >> 1) & 0x3ff) << 21
+             }
 
 
         }
@@ -132,6 +171,12 @@ macro_rules! insn_format {
              fn rs2(&self,code:&TerminusInsnT)->TerminusInsnT {
                 (*code >> 2) & 0x1f
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "rs2"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, _imm:TerminusInsnT)->TerminusInsnT {
+                ((rd | rs1) & 0x1f) << 7 | (rs2 & 0x1f) << 2
+             }
         }
     };
     ($name:ident, CIW) => {
@@ -148,6 +193,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 8
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, _rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rd & 0x7) << 2 | (imm & 0xff) << 5
+             }
         }
     };
     ($name:ident, CI) => {
@@ -168,6 +219,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 6
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                ((rd | rs1) & 0x1f) << 7 | ((imm >> 5) & 0x1) << 12 | (imm & 0x1f) << 2
+             }
 
 
         }
@@ -186,6 +243,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 6
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rs2", "imm"]
+             }
+             fn encode(&self,_rd:TerminusInsnT, _rs1:TerminusInsnT, rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rs2 & 0x1f) << 2 | (imm & 0x3f) << 7
+             }
         }
     };
     ($name:ident, CL) => {
@@ -206,6 +269,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 5
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rd & 0x7) << 2 | (rs1 & 0x7) << 7 | ((imm >> 2) & 0x7) << 10 | (imm & 0x3) << 5
+             }
         }
     };
     ($name:ident, CS) => {
@@ -225,6 +294,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 5
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rs1", "rs2", "imm"]
+             }
+             fn encode(&self,_rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (rs2 & 0x7) << 2 | (rs1 & 0x7) << 7 | ((imm >> 2) & 0x7) << 10 | (imm & 0x3) << 5
+             }
         }
     };
     ($name:ident, CB) => {
@@ -244,6 +319,12 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 8
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "imm"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                ((rd | rs1) & 0x7) << 7 | ((imm >> 5) & 0x7) << 10 | (imm & 0x1f) << 2
+             }
         }
     };
     ($name:ident, CA) => {
@@ -260,6 +341,12 @@ macro_rules! insn_format {
              fn rs1(&self,code:&TerminusInsnT)->TerminusInsnT {
                 ((*code >> 7) & 0x7) + 8
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["rd", "rs1", "rs2"]
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, _imm:TerminusInsnT)->TerminusInsnT {
+                ((rd | rs1) & 0x7) << 7 | (rs2 & 0x7) << 2
+             }
         }
     };
     ($name:ident, CJ) => {
@@ -273,6 +360,47 @@ macro_rules! insn_format {
              fn imm_len(&self)-> usize {
                 11
              }
+             fn fields(&self)->&'static [&'static str] {
+                &["imm"]
+             }
+             fn encode(&self,_rd:TerminusInsnT, _rs1:TerminusInsnT, _rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                (imm & 0x7ff) << 2
+             }
+        }
+    };
+    //defined by define_format!
+    ($name:ident, $format:ident) => {
+        impl Format for $name {
+             fn op(&self,code:&TerminusInsnT)->TerminusInsnT {
+                Format::op(&<$format as DefinedFormat>::FORMAT, code)
+             }
+             fn rd(&self,code:&TerminusInsnT)->TerminusInsnT {
+                Format::rd(&<$format as DefinedFormat>::FORMAT, code)
+             }
+             fn rs1(&self,code:&TerminusInsnT)->TerminusInsnT {
+                Format::rs1(&<$format as DefinedFormat>::FORMAT, code)
+             }
+             fn rs2(&self,code:&TerminusInsnT)->TerminusInsnT {
+                Format::rs2(&<$format as DefinedFormat>::FORMAT, code)
+             }
+             fn imm(&self,code:&TerminusInsnT)->TerminusInsnT {
+                Format::imm(&<$format as DefinedFormat>::FORMAT, code)
+             }
+             fn imm_len(&self)-> usize {
+                Format::imm_len(&<$format as DefinedFormat>::FORMAT)
+             }
+             fn imm_unsigned(&self)-> bool {
+                Format::imm_unsigned(&<$format as DefinedFormat>::FORMAT)
+             }
+             fn imm_shift(&self)-> usize {
+                Format::imm_shift(&<$format as DefinedFormat>::FORMAT)
+             }
+             fn fields(&self)->&'static [&'static str] {
+                Format::fields(&<$format as DefinedFormat>::FORMAT)
+             }
+             fn encode(&self,rd:TerminusInsnT, rs1:TerminusInsnT, rs2:TerminusInsnT, imm:TerminusInsnT)->TerminusInsnT {
+                Format::encode(&<$format as DefinedFormat>::FORMAT, rd, rs1, rs2, imm)
+             }
         }
     };
     ($name:ident, $($t:tt)*) => {
@@ -9,5 +9,3 @@ pub use lazy_static::*;
 
 mod insn;
 mod terminus_insn;
-pub use insn::*;
-pub use terminus_insn::*;
@@ -0,0 +1,323 @@
+#[macro_export(local_inner_macros)]
+macro_rules! init_assembler {
+    ($inst:ty) => {
+        /// An assembly error and the 1-based line it was found on.
+        #[derive(Debug, Eq, PartialEq)]
+        pub struct AsmError {
+            pub line: usize,
+            pub msg: String,
+        }
+
+        impl AsmError {
+            fn new(line: usize, msg: String) -> AsmError {
+                AsmError { line, msg }
+            }
+        }
+
+        impl std::fmt::Display for AsmError {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(&__terminus_insn_format!("line {}: {}", self.line, self.msg))
+            }
+        }
+
+        enum AsmPiece<'a> {
+            Lit(String),
+            Operand(&'a str, Option<&'a str>),
+        }
+
+        /// Assembles the mnemonics of the `#[asm]` templates of a map. Numeric and ABI register
+        /// names, labels and `.word` are accepted, label operands are encoded as pc-relative
+        /// offsets and `#` starts a comment.
+        pub struct Assembler<'a> {
+            insns: std::collections::HashMap<String, Vec<&'a dyn Decoder>>,
+        }
+
+        impl<'a> Assembler<'a> {
+            const X_ABI: [&'static str; 32] = [
+                "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2",
+                "a3", "a4", "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9",
+                "s10", "s11", "t3", "t4", "t5", "t6",
+            ];
+            const F_ABI: [&'static str; 32] = [
+                "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1",
+                "fa2", "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7",
+                "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+            ];
+
+            pub fn new<M: InsnMap>(map: &'a M) -> Assembler<'a> {
+                let mut decoders = map.iter().collect::<Vec<_>>();
+                decoders.sort_by_key(|d| d.name());
+                let mut insns = std::collections::HashMap::new();
+                for d in decoders {
+                    let name = d.name().to_lowercase();
+                    let mnemonic = Self::mnemonic(d.decode().asm()).to_lowercase();
+                    if mnemonic != name {
+                        insns.entry(name).or_insert_with(Vec::new).push(d);
+                    }
+                    insns.entry(mnemonic).or_insert_with(Vec::new).push(d);
+                }
+                Assembler { insns }
+            }
+
+            pub fn assemble(&self, src: &str) -> Result<Vec<$inst>, AsmError> {
+                let mut labels = std::collections::HashMap::new();
+                let mut stmts = Vec::new();
+                let mut pc: u64 = 0;
+                for (i, raw) in src.lines().enumerate() {
+                    let line = i + 1;
+                    let mut text = raw.split('#').next().unwrap_or("").trim();
+                    while let Some(pos) = text.find(':') {
+                        let label = text[..pos].trim();
+                        if !Self::is_label(label) {
+                            return Err(AsmError::new(
+                                line,
+                                __terminus_insn_format!("invalid label \"{}\"!", label),
+                            ));
+                        }
+                        if labels.insert(label.to_string(), pc).is_some() {
+                            return Err(AsmError::new(
+                                line,
+                                __terminus_insn_format!("label \"{}\" is redefined!", label),
+                            ));
+                        }
+                        text = text[pos + 1..].trim();
+                    }
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let mnemonic = Self::mnemonic(text);
+                    let operands = text[mnemonic.len()..].trim();
+                    let mnemonic = mnemonic.to_lowercase();
+                    let size = if mnemonic == ".word" {
+                        4 * operands.split(',').count() as u64
+                    } else if let Some(d) = self.insns.get(&mnemonic).and_then(|c| c.first()) {
+                        insn_len(d.code() as u16).unwrap_or(4) as u64
+                    } else {
+                        return Err(AsmError::new(
+                            line,
+                            __terminus_insn_format!("unknown instruction \"{}\"!", mnemonic),
+                        ));
+                    };
+                    stmts.push((line, pc, mnemonic, operands));
+                    pc += size;
+                }
+                let mut codes = Vec::new();
+                for (line, pc, mnemonic, operands) in stmts {
+                    if mnemonic == ".word" {
+                        for value in operands.split(',') {
+                            let value = value.trim();
+                            let code = Self::number(value)
+                                .or_else(|| labels.get(value).map(|addr| *addr as i128))
+                                .ok_or_else(|| {
+                                    AsmError::new(
+                                        line,
+                                        __terminus_insn_format!("invalid value \"{}\"!", value),
+                                    )
+                                })?;
+                            codes.push(code as $inst);
+                        }
+                    } else {
+                        let operands = operands
+                            .chars()
+                            .filter(|c| !c.is_whitespace())
+                            .collect::<String>();
+                        let mut err = String::new();
+                        let mut code = None;
+                        for d in self.insns[&mnemonic].iter() {
+                            match Self::encode(*d, pc, &operands, &labels) {
+                                Ok(c) => {
+                                    code = Some(c);
+                                    break;
+                                }
+                                Err(e) => err = e,
+                            }
+                        }
+                        codes.push(code.ok_or_else(|| AsmError::new(line, err))?);
+                    }
+                }
+                Ok(codes)
+            }
+
+            fn encode(
+                d: &dyn Decoder,
+                pc: u64,
+                operands: &str,
+                labels: &std::collections::HashMap<String, u64>,
+            ) -> Result<$inst, String> {
+                let template = d.decode().asm().trim();
+                let pieces = Self::pieces(template[Self::mnemonic(template).len()..].trim())?;
+                let mut fields: [Option<(&str, i128)>; 4] = [None; 4];
+                let mut input = operands;
+                for (i, piece) in pieces.iter().enumerate() {
+                    match piece {
+                        AsmPiece::Lit(lit) => {
+                            input = input.strip_prefix(lit.as_str()).ok_or_else(|| {
+                                __terminus_insn_format!(
+                                    "expect \"{}\" at \"{}\", syntax is \"{}\"!",
+                                    lit,
+                                    input,
+                                    template
+                                )
+                            })?;
+                        }
+                        AsmPiece::Operand(field, spec) => {
+                            let end = match pieces.get(i + 1) {
+                                Some(AsmPiece::Lit(lit)) => {
+                                    input.find(lit.as_str()).unwrap_or(input.len())
+                                }
+                                _ => input.len(),
+                            };
+                            let token = &input[..end];
+                            input = &input[end..];
+                            let (idx, value) = match *field {
+                                "rd" => (0, Self::register(token, spec.unwrap_or("x"))),
+                                "rs1" => (1, Self::register(token, spec.unwrap_or("x"))),
+                                "rs2" => (2, Self::register(token, spec.unwrap_or("x"))),
+                                _ => (
+                                    3,
+                                    Self::number(token).or_else(|| {
+                                        labels.get(token).map(|addr| *addr as i128 - pc as i128)
+                                    }),
+                                ),
+                            };
+                            let value = value.ok_or_else(|| {
+                                __terminus_insn_format!(
+                                    "invalid operand \"{}\" for \"{{{}}}\"!",
+                                    token,
+                                    field
+                                )
+                            })?;
+                            fields[idx] = Some((token, value));
+                        }
+                    }
+                }
+                if !input.is_empty() {
+                    return Err(__terminus_insn_format!(
+                        "unexpected \"{}\", syntax is \"{}\"!",
+                        input,
+                        template
+                    ));
+                }
+                let insn = d.decode();
+                let shift = insn.imm_shift() as u32;
+                let value = |i: usize| fields[i].map(|(_, v)| v as $inst).unwrap_or(0);
+                let code = d.encode(value(0), value(1), value(2), value(3) << shift);
+                let decoded = [insn.rd(&code), insn.rs1(&code), insn.rs2(&code)];
+                for (i, field) in fields[..3].iter().enumerate() {
+                    if let Some((token, v)) = field {
+                        if decoded[i] as i128 != *v {
+                            return Err(__terminus_insn_format!(
+                                "register \"{}\" can not be encoded!",
+                                token
+                            ));
+                        }
+                    }
+                }
+                if let Some((token, v)) = fields[3] {
+                    let imm = insn.imm_value(&code) as i128;
+                    if v != imm >> shift && v != insn.imm_signed(&code) >> shift {
+                        return Err(__terminus_insn_format!(
+                            "immediate \"{}\" can not be encoded!",
+                            token
+                        ));
+                    }
+                }
+                Ok(code)
+            }
+
+            fn pieces(template: &str) -> Result<Vec<AsmPiece>, String> {
+                let mut pieces = Vec::new();
+                let mut text = String::new();
+                let mut rest = template;
+                while let Some(c) = rest.chars().next() {
+                    if rest.starts_with("{{") || rest.starts_with("}}") {
+                        text.push(c);
+                        rest = &rest[2..];
+                    } else if c == '{' {
+                        let end = rest.find('}').ok_or_else(|| {
+                            __terminus_insn_format!("invalid asm template \"{}\"!", template)
+                        })?;
+                        let mut operand = rest[1..end].splitn(2, ':');
+                        if !text.is_empty() {
+                            pieces.push(AsmPiece::Lit(std::mem::take(&mut text)));
+                        }
+                        pieces.push(AsmPiece::Operand(
+                            operand.next().unwrap_or(""),
+                            operand.next(),
+                        ));
+                        rest = &rest[end + 1..];
+                    } else {
+                        if !c.is_whitespace() {
+                            text.push(c);
+                        }
+                        rest = &rest[c.len_utf8()..];
+                    }
+                }
+                if !text.is_empty() {
+                    pieces.push(AsmPiece::Lit(text));
+                }
+                Ok(pieces)
+            }
+
+            fn mnemonic(text: &str) -> &str {
+                text.split_whitespace().next().unwrap_or("")
+            }
+
+            fn is_label(label: &str) -> bool {
+                let mut chars = label.chars();
+                chars
+                    .next()
+                    .map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            }
+
+            fn register(token: &str, file: &str) -> Option<i128> {
+                let abi: &[&str] = match file {
+                    "x" => &Self::X_ABI,
+                    "f" => &Self::F_ABI,
+                    _ => &[],
+                };
+                if let Some(idx) = abi.iter().position(|r| *r == token) {
+                    return Some(idx as i128);
+                }
+                if file == "x" && token == "fp" {
+                    return Some(8);
+                }
+                token
+                    .strip_prefix(file)
+                    .filter(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .filter(|n| *n < 32)
+                    .map(|n| n as i128)
+            }
+
+            fn number(token: &str) -> Option<i128> {
+                let (neg, digits) = match token.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, token),
+                };
+                let value = if let Some(hex) = digits
+                    .strip_prefix("0x")
+                    .or_else(|| digits.strip_prefix("0X"))
+                {
+                    i128::from_str_radix(hex, 16).ok()
+                } else if let Some(bin) = digits
+                    .strip_prefix("0b")
+                    .or_else(|| digits.strip_prefix("0B"))
+                {
+                    i128::from_str_radix(bin, 2).ok()
+                } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+                    digits.parse::<i128>().ok()
+                } else {
+                    None
+                }?;
+                Some(if neg { -value } else { value })
+            }
+        }
+
+        /// Assembles src with the instructions of `GDECODER`.
+        pub fn assemble(src: &str) -> Result<Vec<$inst>, AsmError> {
+            Assembler::new(&*GDECODER).assemble(src)
+        }
+    };
+}
@@ -0,0 +1,125 @@
+#[macro_export(local_inner_macros)]
+macro_rules! init_cache {
+    ($inst:ty) => {
+        pub struct BlockInsn<'a> {
+            pub pc: u64,
+            pub len: usize,
+            pub ir: $inst,
+            pub insn: &'a Instruction,
+        }
+
+        /// Instructions decoded from pc up to and including the first one leaving straight-line
+        /// execution, end is the address after the last one.
+        pub struct DecodedBlock<'a> {
+            pub pc: u64,
+            pub end: u64,
+            pub insns: Vec<BlockInsn<'a>>,
+        }
+
+        /// Decoded blocks keyed by their start pc, writes to instruction memory must be reported by
+        /// invalidate and fence.i by fence_i.
+        pub struct BlockCache<'a, M: InsnMap> {
+            map: &'a M,
+            blocks: std::collections::HashMap<u64, DecodedBlock<'a>>,
+            max_insns: usize,
+        }
+
+        impl<'a, M: InsnMap> BlockCache<'a, M> {
+            pub fn new(map: &'a M) -> BlockCache<'a, M> {
+                BlockCache {
+                    map,
+                    blocks: std::collections::HashMap::new(),
+                    max_insns: 64,
+                }
+            }
+
+            pub fn max_insns(mut self, max_insns: usize) -> BlockCache<'a, M> {
+                self.max_insns = max_insns.max(1);
+                self
+            }
+
+            fn block_end(insn: &Instruction) -> bool {
+                match insn.class() {
+                    InsnClass::Branch
+                    | InsnClass::Jump
+                    | InsnClass::Call
+                    | InsnClass::Return
+                    | InsnClass::Fence
+                    | InsnClass::System => true,
+                    _ => false,
+                }
+            }
+
+            //fetch returns the 16-bit parcel at an address, the block stops before the first
+            //instruction that can not be fetched or decoded, which is only an error at pc
+            fn fetch_insn<F: FnMut(u64) -> Option<u16>>(
+                &self,
+                pc: u64,
+                fetch: &mut F,
+            ) -> Result<BlockInsn<'a>, Error> {
+                let parcel = fetch(pc).ok_or(Error::Truncated(0, 2))?;
+                let len = match insn_len(parcel) {
+                    Some(len) if len > std::mem::size_of::<$inst>() => {
+                        return Err(Error::Illegal(parcel as $inst))
+                    }
+                    Some(len) => len,
+                    None => return Err(Error::Illegal(parcel as $inst)),
+                };
+                let mut ir = parcel as $inst;
+                for i in 1..len / 2 {
+                    match fetch(pc + 2 * i as u64) {
+                        Some(p) => ir |= (p as $inst).checked_shl(16 * i as u32).unwrap_or(0),
+                        None => return Err(Error::Truncated(ir, len)),
+                    }
+                }
+                let insn = self.map.decode(&ir)?;
+                Ok(BlockInsn { pc, len, ir, insn })
+            }
+
+            /// The block starting at pc, decoded through fetch on a miss.
+            pub fn get<F: FnMut(u64) -> Option<u16>>(
+                &mut self,
+                pc: u64,
+                mut fetch: F,
+            ) -> Result<&DecodedBlock<'a>, Error> {
+                if !self.blocks.contains_key(&pc) {
+                    let first = self.fetch_insn(pc, &mut fetch)?;
+                    let mut end = pc + first.len as u64;
+                    let mut stop = Self::block_end(first.insn);
+                    let mut insns = Vec::new();
+                    insns.push(first);
+                    while !stop && insns.len() < self.max_insns {
+                        match self.fetch_insn(end, &mut fetch) {
+                            Ok(insn) => {
+                                end += insn.len as u64;
+                                stop = Self::block_end(insn.insn);
+                                insns.push(insn);
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                    self.blocks.insert(pc, DecodedBlock { pc, end, insns });
+                }
+                Ok(&self.blocks[&pc])
+            }
+
+            /// Drops every block overlapping the len bytes written at addr.
+            pub fn invalidate(&mut self, addr: u64, len: usize) {
+                let end = addr.saturating_add(len as u64);
+                self.blocks.retain(|_, b| b.end <= addr || b.pc >= end);
+            }
+
+            pub fn fence_i(&mut self) {
+                self.blocks.clear();
+            }
+
+            pub fn len(&self) -> usize {
+                self.blocks.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.blocks.is_empty()
+            }
+        }
+    };
+}
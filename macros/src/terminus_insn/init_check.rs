@@ -0,0 +1,139 @@
+#[macro_export(local_inner_macros)]
+macro_rules! init_check {
+    ($inst:ty) => {
+        /// A problem found by `self_check`.
+        /// - Length: the code's length bits disagree with the length of its `#[code]` string
+        /// - Mismatch: a word of inst decodes to neither inst nor an inst taking precedence over it
+        /// - Unreachable: neither the code nor any random word of inst decodes to inst, or inst is
+        ///   32-bit and the rng gives up drawing 32-bit words
+        /// - Disagree: the tree map, the simple map and the checked map decode a word differently
+        #[derive(Debug, Clone, Eq, PartialEq)]
+        pub enum CheckError {
+            Length(String, $inst, usize),
+            Mismatch(String, $inst, String),
+            Unreachable(String),
+            Disagree($inst, String, String, String),
+        }
+
+        impl std::fmt::Display for CheckError {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(&match self {
+                    CheckError::Length(name, code, len) => __terminus_insn_format!(
+                        "inst {}(code = {:#x}) is declared with {} bytes but its code encodes {:?} bytes",
+                        name,
+                        code,
+                        len,
+                        insn_len(*code as u16)
+                    ),
+                    CheckError::Mismatch(name, ir, decoded) => {
+                        __terminus_insn_format!("{:#x} of inst {} decodes to {}", ir, name, decoded)
+                    }
+                    CheckError::Unreachable(name) => {
+                        __terminus_insn_format!("inst {} is never decoded", name)
+                    }
+                    CheckError::Disagree(ir, tree, simple, map) => __terminus_insn_format!(
+                        "{:#x} decodes to {} in tree map, {} in simple map and {} in checked map",
+                        ir,
+                        tree,
+                        simple,
+                        map
+                    ),
+                })
+            }
+        }
+
+        fn check_outcome(result: Result<&Instruction, Error>) -> String {
+            match result {
+                Ok(insn) => insn.name().to_string(),
+                Err(e) => e.to_string(),
+            }
+        }
+
+        /// Checks a locked map, meant for `#[test]`s: every inst decodes its own code and random
+        /// words of its pattern, and tree and simple maps holding the same insts agree with it on
+        /// every 16-bit word and on samples random 32-bit words.
+        pub fn self_check<M: InsnMap, R: InsnRng>(map: &M, rng: &mut R, samples: usize) -> Vec<CheckError> {
+            let mut errors = Vec::new();
+            for d in map.iter() {
+                let info = d.info();
+                if insn_len(d.code() as u16) != Some(info.len) {
+                    errors.push(CheckError::Length(d.name(), d.code(), info.len));
+                }
+                let mut reached = false;
+                let mut mismatched = false;
+                let words = std::iter::once(d.code())
+                    .chain((0..RANDOM_TRIES).map(|_| d.random(rng)))
+                    .collect::<Vec<_>>();
+                for ir in words {
+                    match map.decode(&ir) {
+                        Ok(insn) if std::ptr::eq(insn, d.decode()) => reached = true,
+                        Ok(insn)
+                            if map.iter().any(|w| {
+                                std::ptr::eq(insn, w.decode())
+                                    && w.matched(&ir)
+                                    && w.precedence(d) == std::cmp::Ordering::Less
+                            }) => {}
+                        //a reserved word of inst neither reaches it nor decodes to another inst
+                        Err(Error::Reserved(_, ref name)) if *name == d.name() => {}
+                        result => {
+                            if !mismatched {
+                                errors.push(CheckError::Mismatch(d.name(), ir, check_outcome(result)));
+                                mismatched = true;
+                            }
+                        }
+                    }
+                }
+                if !reached && !mismatched {
+                    errors.push(CheckError::Unreachable(d.name()));
+                }
+            }
+
+            let same = |a: &dyn Decoder, b: &dyn Decoder| {
+                a.name() == b.name() && a.code() == b.code() && a.mask() == b.mask() && a.xlen() == b.xlen()
+            };
+            let mut tree = TreeInsnMap::new();
+            let mut simple = SimpleInsnMap::new();
+            for r in REGISTERY_INSN {
+                let decoder = r();
+                if map.iter().any(|d| same(d, decoder.as_ref())) {
+                    tree.registery(decoder);
+                    simple.registery(r());
+                }
+            }
+            tree.lock();
+            simple.lock();
+            let mut words = (0..=u16::MAX as u32).map(|ir| ir as $inst).collect::<Vec<_>>();
+            if std::mem::size_of::<$inst>() >= 4 {
+                let mut tries = samples * RANDOM_TRIES;
+                while words.len() < samples + (1 << 16) && tries > 0 {
+                    let ir = (rng.next_u64() & 0xffff_ffff) as $inst | 0x3;
+                    if insn_len(ir as u16) == Some(4) {
+                        words.push(ir);
+                    }
+                    tries -= 1;
+                }
+                //the 32-bit insts are left unchecked by an rng that draws no 32-bit words
+                if words.len() < samples + (1 << 16) {
+                    for d in map.iter().filter(|d| d.info().len == 4) {
+                        let unreachable = CheckError::Unreachable(d.name());
+                        if !errors.contains(&unreachable) {
+                            errors.push(unreachable);
+                        }
+                    }
+                }
+            }
+            for ir in words {
+                let m = match map.decode(&ir) {
+                    //the reference maps hold no disabled insts, a disabled word is illegal to them
+                    Err(Error::Disabled(ir, _)) => check_outcome(Err(Error::Illegal(ir))),
+                    result => check_outcome(result),
+                };
+                let (t, s) = (check_outcome(tree.decode(&ir)), check_outcome(simple.decode(&ir)));
+                if t != s || t != m {
+                    errors.push(CheckError::Disagree(ir, t, s, m));
+                }
+            }
+            errors
+        }
+    };
+}
@@ -1,15 +1,93 @@
 #[macro_export(local_inner_macros)]
 macro_rules! init_decoder {
     ($inst:ty) => {
+        /// Why a word failed to decode. Illegal is an unallocated word, Reserved and Disabled carry
+        /// the name of the matched instruction, Ambiguous the names of two instructions of different
+        /// XLENs that only an XLEN can choose between, Truncated carries the bytes fetched so far and
+        /// the length in bytes the instruction needs.
         #[derive(Debug, Eq, PartialEq)]
         pub enum Error {
             Illegal($inst),
+            Reserved($inst, String),
+            Disabled($inst, String),
+            Ambiguous($inst, String, String),
+            Truncated($inst, usize),
         }
 
         impl Error {
             pub fn ir(&self) -> $inst {
                 match self {
-                    Error::Illegal(ir) => *ir,
+                    Error::Illegal(ir)
+                    | Error::Reserved(ir, _)
+                    | Error::Disabled(ir, _)
+                    | Error::Ambiguous(ir, _, _)
+                    | Error::Truncated(ir, _) => *ir,
+                }
+            }
+
+            pub fn disasm(&self) -> String {
+                __terminus_insn_format!(".word {:#x}", self.ir())
+            }
+        }
+
+        impl std::fmt::Display for Error {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(&match self {
+                    Error::Illegal(ir) => __terminus_insn_format!("illegal instruction {:#x}", ir),
+                    Error::Reserved(ir, name) => {
+                        __terminus_insn_format!("reserved encoding {:#x} of inst {}", ir, name)
+                    }
+                    Error::Disabled(ir, name) => {
+                        __terminus_insn_format!("inst {}({:#x}) is not enabled", name, ir)
+                    }
+                    Error::Ambiguous(ir, name, other) => __terminus_insn_format!(
+                        "{:#x} decodes to inst {} or inst {} depending on XLEN",
+                        ir, name, other
+                    ),
+                    Error::Truncated(ir, len) => {
+                        __terminus_insn_format!("truncated instruction {:#x}, {} bytes expected", ir, len)
+                    }
+                })
+            }
+        }
+
+        /// What a map consults besides the decoder it picks for a word: the `Conflict::xlen_tie`s
+        /// of its decoders, and the linked instructions of its XLEN its builder disabled, kept in
+        /// a tree of their own.
+        #[derive(Default)]
+        pub struct DecodeContext {
+            ties: Vec<Conflict>,
+            disabled: Option<Box<TreeInsnMap>>,
+        }
+
+        impl DecodeContext {
+            pub fn disable<T: 'static + Decoder>(&mut self, decoder: T) {
+                self.disabled
+                    .get_or_insert_with(|| Box::new(TreeInsnMap::new()))
+                    .registery(decoder)
+            }
+
+            pub fn lock(&mut self) {
+                if let Some(disabled) = self.disabled.as_mut() {
+                    disabled.lock()
+                }
+            }
+
+            /// Turns the decoder the map picked for ir into the decode result.
+            pub fn result<'a>(&self, decoder: Option<&'a dyn Decoder>, ir: &$inst) -> Result<&'a Instruction, Error> {
+                if let Some(t) = self.ties.iter().find(|t| *ir & t.mask == t.code && *ir & t.other_mask == t.other_code) {
+                    return Err(Error::Ambiguous(*ir, t.name.clone(), t.other_name.clone()));
+                }
+                match decoder {
+                    Some(d) if d.reserved(ir) => Err(Error::Reserved(*ir, d.name())),
+                    Some(d) => {
+                        __terminus_insn_stats!(record_stats(d););
+                        Ok(d.decode())
+                    }
+                    None => match self.disabled.as_ref().and_then(|m| m.find(ir)) {
+                        Some(d) => Err(Error::Disabled(*ir, d.name())),
+                        None => Err(Error::Illegal(*ir)),
+                    },
                 }
             }
         }
@@ -20,26 +98,489 @@ macro_rules! init_decoder {
             fn matched(&self, ir: &$inst) -> bool;
             fn decode(&self) -> &Instruction;
             fn name(&self) -> String;
+            fn reserved(&self, _ir: &$inst) -> bool {
+                false
+            }
+            fn priority(&self) -> i32 {
+                0
+            }
+            /// The `#[extension]` tag, "" means the instruction is not tagged and always enabled.
+            fn extension(&self) -> &'static str {
+                ""
+            }
+            /// The `#[xlen]` tag, 0 means the instruction exists in every XLEN.
+            fn xlen(&self) -> usize {
+                0
+            }
+            /// The decode hits of this decoder in its map, counted with the "stats" feature.
+            fn hits(&self) -> Option<&std::sync::atomic::AtomicU64> {
+                None
+            }
+            fn info(&self) -> InsnInfo {
+                InsnInfo {
+                    name: self.name(),
+                    format: "",
+                    code: self.code(),
+                    mask: self.mask(),
+                    extension: self.extension(),
+                    xlen: self.xlen(),
+                    priority: self.priority(),
+                    class: self.decode().class(),
+                    fields: self.decode().fields(),
+                    imm: self.decode().imm_layout(),
+                    len: insn_len(self.code() as u16).unwrap_or(std::mem::size_of::<$inst>()),
+                    file: "",
+                    line: 0,
+                }
+            }
+            fn xlen_compatible(&self, other: &dyn Decoder) -> bool {
+                self.xlen() == 0 || other.xlen() == 0 || self.xlen() == other.xlen()
+            }
+            /// Less means self wins: higher priority first, then the mask with more fixed bits, then name.
+            fn precedence(&self, other: &dyn Decoder) -> std::cmp::Ordering {
+                other
+                    .priority()
+                    .cmp(&self.priority())
+                    .then_with(|| other.mask().count_ones().cmp(&self.mask().count_ones()))
+                    .then_with(|| self.name().cmp(&other.name()))
+            }
+            fn encode(&self, rd: $inst, rs1: $inst, rs2: $inst, imm: $inst) -> $inst {
+                let insn = self.decode();
+                insn.encode(rd, rs1, rs2, insn.imm_field(imm)) & !self.mask() | self.code()
+            }
+            /// Code with random bits outside mask, bits beyond the instruction length are 0.
+            fn random(&self, rng: &mut dyn InsnRng) -> $inst {
+                let bits = std::mem::size_of::<$inst>() << 3;
+                let len = insn_len(self.code() as u16).map_or(bits, |len| (len << 3).min(bits));
+                let valid = if len >= bits {
+                    !(0 as $inst)
+                } else {
+                    ((1 as $inst) << len) - 1
+                };
+                let word = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) as $inst;
+                word & valid & !self.mask() | self.code()
+            }
+        }
+
+        /// The random source of `random_insn` and `self_check`, any `FnMut() -> u64` is one.
+        pub trait InsnRng {
+            fn next_u64(&mut self) -> u64;
+        }
+
+        impl<F: FnMut() -> u64> InsnRng for F {
+            fn next_u64(&mut self) -> u64 {
+                self()
+            }
+        }
+
+        impl Decoder for Box<dyn Decoder> {
+            fn code(&self) -> $inst {
+                self.as_ref().code()
+            }
+            fn mask(&self) -> $inst {
+                self.as_ref().mask()
+            }
+            fn matched(&self, ir: &$inst) -> bool {
+                self.as_ref().matched(ir)
+            }
+            fn decode(&self) -> &Instruction {
+                self.as_ref().decode()
+            }
+            fn name(&self) -> String {
+                self.as_ref().name()
+            }
+            fn reserved(&self, ir: &$inst) -> bool {
+                self.as_ref().reserved(ir)
+            }
+            fn priority(&self) -> i32 {
+                self.as_ref().priority()
+            }
+            fn extension(&self) -> &'static str {
+                self.as_ref().extension()
+            }
+            fn xlen(&self) -> usize {
+                self.as_ref().xlen()
+            }
+            fn hits(&self) -> Option<&std::sync::atomic::AtomicU64> {
+                self.as_ref().hits()
+            }
+            fn info(&self) -> InsnInfo {
+                self.as_ref().info()
+            }
+        }
+
+        /// Metadata of an instruction listed by `InsnMap::infos`. len is in bytes, file and line
+        /// locate the instruction definition.
+        #[derive(Debug, Clone, Eq, PartialEq)]
+        pub struct InsnInfo {
+            pub name: String,
+            pub format: &'static str,
+            pub code: $inst,
+            pub mask: $inst,
+            pub extension: &'static str,
+            pub xlen: usize,
+            pub priority: i32,
+            pub class: InsnClass,
+            pub fields: &'static [&'static str],
+            pub imm: &'static str,
+            pub len: usize,
+            pub file: &'static str,
+            pub line: u32,
+        }
+
+        /// Two registered instructions that both match some word.
+        #[derive(Debug, Clone, Eq, PartialEq)]
+        pub struct Conflict {
+            pub name: String,
+            pub code: $inst,
+            pub mask: $inst,
+            pub other_name: String,
+            pub other_code: $inst,
+            pub other_mask: $inst,
+        }
+
+        impl Conflict {
+            fn new(a: &dyn Decoder, b: &dyn Decoder) -> Conflict {
+                Conflict {
+                    name: a.name(),
+                    code: a.code(),
+                    mask: a.mask(),
+                    other_name: b.name(),
+                    other_code: b.code(),
+                    other_mask: b.mask(),
+                }
+            }
+
+            /// a and b of different XLENs both match some word and neither wins by priority or mask
+            /// specificity, a map without an XLEN holding both can not decode that word.
+            pub fn xlen_tie(a: &dyn Decoder, b: &dyn Decoder) -> Option<Conflict> {
+                if (a.code() ^ b.code()) & a.mask() & b.mask() == 0
+                    && a.priority() == b.priority()
+                    && a.mask().count_ones() == b.mask().count_ones()
+                    && !a.xlen_compatible(b)
+                {
+                    Some(if a.name() < b.name() {
+                        Conflict::new(a, b)
+                    } else {
+                        Conflict::new(b, a)
+                    })
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl std::fmt::Display for Conflict {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(&__terminus_insn_format!(
+                    "inst {}(code = {:#x}; mask = {:#x}) overlaps with inst {}(code = {:#x}; mask = {:#x})",
+                    self.name, self.code, self.mask, self.other_name, self.other_code, self.other_mask
+                ))
+            }
+        }
+
+        /// What `InsnMap::check_conflicts` does about the conflicts it finds.
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        pub enum ConflictPolicy {
+            Panic,
+            Warn,
+            Ignore,
         }
 
+        /// A map from words to the registered instructions. `GDECODER` holds every linked
+        /// instruction, other maps can be built from `REGISTERY_INSN` or by `DecoderBuilder` and
+        /// dropped at will.
         pub trait InsnMap {
             fn registery<T: 'static + Decoder>(&mut self, decoder: T);
             fn decode(&self, ir: &$inst) -> Result<&Instruction, Error>;
+            fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a dyn Decoder> + 'a>;
             fn lock(&mut self) {}
+            /// Registers every linked instruction filter accepts.
+            fn registery_linked<F: Fn(&dyn Decoder) -> bool>(&mut self, filter: F) {
+                for r in REGISTERY_INSN {
+                    let decoder = r();
+                    if filter(decoder.as_ref()) {
+                        self.registery(decoder)
+                    }
+                }
+            }
+            /// Records a linked instruction left out of the map, the words only it matches decode
+            /// as `Error::Disabled` instead of `Error::Illegal`.
+            fn disable<T: 'static + Decoder>(&mut self, _decoder: T) {}
+            /// Disables every linked instruction filter accepts.
+            fn disable_linked<F: Fn(&dyn Decoder) -> bool>(&mut self, filter: F) {
+                for r in REGISTERY_INSN {
+                    let decoder = r();
+                    if filter(decoder.as_ref()) {
+                        self.disable(decoder)
+                    }
+                }
+            }
+            /// A pre-decoded instance of the instruction ir decodes to.
+            fn decode_owned(&self, ir: &$inst) -> Result<Box<dyn InstructionImp>, Error> {
+                self.decode(ir).map(|insn| insn.decode_owned(ir))
+            }
+            /// The `#[asm]` template of the instruction filled from ir, `.word` if it does not decode.
+            fn disasm(&self, ir: &$inst) -> String {
+                match self.decode(ir) {
+                    Ok(insn) => insn.disasm(ir),
+                    Err(e) => e.disasm(),
+                }
+            }
+            /// Every pair of instructions that both match some word.
+            fn conflicts(&self) -> Vec<Conflict> {
+                let mut decoders = self.iter().collect::<Vec<_>>();
+                decoders.sort_by_key(|d| (d.name(), d.code(), d.mask()));
+                let mut conflicts = Vec::new();
+                for (i, a) in decoders.iter().enumerate() {
+                    for b in decoders[i + 1..].iter() {
+                        if (a.code() ^ b.code()) & a.mask() & b.mask() == 0 {
+                            conflicts.push(Conflict::new(*a, *b))
+                        }
+                    }
+                }
+                conflicts
+            }
+            /// The `conflicts` neither `#[priority]` nor mask specificity resolves. Those of the same
+            /// XLEN are ordered by name, those of different XLENs decode as `Error::Ambiguous`.
+            fn ambiguities(&self) -> Vec<Conflict> {
+                let mut decoders = self.iter().collect::<Vec<_>>();
+                decoders.sort_by_key(|d| (d.name(), d.code(), d.mask()));
+                let mut ambiguities = Vec::new();
+                for (i, a) in decoders.iter().enumerate() {
+                    for b in decoders[i + 1..].iter() {
+                        if (a.code() ^ b.code()) & a.mask() & b.mask() == 0
+                            && a.priority() == b.priority()
+                            && a.mask().count_ones() == b.mask().count_ones()
+                        {
+                            ambiguities.push(Conflict::new(*a, *b))
+                        }
+                    }
+                }
+                ambiguities
+            }
+            /// An `InsnInfo` for every instruction, sorted by name.
+            fn infos(&self) -> Vec<InsnInfo> {
+                let mut infos = self.iter().map(|d| d.info()).collect::<Vec<_>>();
+                infos.sort_by(|a, b| (&a.name, a.code, a.mask).cmp(&(&b.name, b.code, b.mask)));
+                infos
+            }
+            /// Registered instructions of the same length as ir ordered by the Hamming distance
+            /// between ir and their code under their mask, the n closest ones are returned.
+            fn nearest<'a>(&'a self, ir: &$inst, n: usize) -> Vec<(u32, &'a dyn Decoder)> {
+                let len = insn_len(*ir as u16);
+                let mut decoders = self
+                    .iter()
+                    .filter(|d| insn_len(d.code() as u16) == len)
+                    .map(|d| (((*ir & d.mask()) ^ d.code()).count_ones(), d))
+                    .collect::<Vec<_>>();
+                decoders.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.name().cmp(&b.1.name())));
+                decoders.truncate(n);
+                decoders
+            }
+            /// A random word decoded to decoder, which must come from iter(), and accepted by filter,
+            /// None if none is found in `RANDOM_TRIES` attempts.
+            fn random_of<R: InsnRng, F: Fn(&dyn Decoder, &$inst) -> bool>(
+                &self,
+                decoder: &dyn Decoder,
+                rng: &mut R,
+                filter: F,
+            ) -> Option<$inst> {
+                (0..RANDOM_TRIES).map(|_| decoder.random(rng)).find(|ir| {
+                    self.decode(ir)
+                        .map_or(false, |insn| std::ptr::eq(insn, decoder.decode()))
+                        && filter(decoder, ir)
+                })
+            }
+            /// A random word of a randomly picked instruction, instructions are tried in random
+            /// order until one of them produces a word accepted by filter.
+            fn random_insn<R: InsnRng, F: Fn(&dyn Decoder, &$inst) -> bool>(
+                &self,
+                rng: &mut R,
+                filter: F,
+            ) -> Option<$inst> {
+                let mut decoders = self.iter().collect::<Vec<_>>();
+                while !decoders.is_empty() {
+                    let decoder = decoders.swap_remove(rng.next_u64() as usize % decoders.len());
+                    if let Some(ir) = self.random_of(decoder, rng, &filter) {
+                        return Some(ir);
+                    }
+                }
+                None
+            }
+            /// `conflicts`, also panicking on them or printing them as warnings as policy says.
+            fn check_conflicts(&self, policy: ConflictPolicy) -> Vec<Conflict> {
+                let conflicts = self.conflicts();
+                match policy {
+                    ConflictPolicy::Panic if !conflicts.is_empty() => __terminus_insn_panic!(
+                        "{}!",
+                        conflicts.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("!\n")
+                    ),
+                    ConflictPolicy::Warn => conflicts.iter().for_each(|c| __terminus_insn_eprintln!("warning: {}!", c)),
+                    _ => {}
+                }
+                conflicts
+            }
+            /// Decodes the little-endian instruction at offset, its length is given by the RISC-V
+            /// length encoding of its first parcel.
+            fn decode_at(&self, bytes: &[u8], offset: usize) -> Result<DecodedInsn<'_>, Error> {
+                let bytes = bytes.get(offset..).unwrap_or(&[]);
+                let word = |len: usize| {
+                    bytes
+                        .iter()
+                        .take(len.min(std::mem::size_of::<$inst>()))
+                        .rev()
+                        .fold(0 as $inst, |acc, b| {
+                            acc.checked_shl(8).unwrap_or(0) | *b as $inst
+                        })
+                };
+                if bytes.len() < 2 {
+                    return Err(Error::Truncated(word(bytes.len()), 2));
+                }
+                let len = match insn_len(bytes[0] as u16 | (bytes[1] as u16) << 8) {
+                    Some(len) if len > std::mem::size_of::<$inst>() => {
+                        return Err(Error::Illegal(word(len)))
+                    }
+                    Some(len) if len > bytes.len() => {
+                        return Err(Error::Truncated(word(bytes.len()), len))
+                    }
+                    Some(len) => len,
+                    None => return Err(Error::Illegal(word(2))),
+                };
+                let ir = word(len);
+                self.decode(&ir).map(|insn| DecodedInsn {
+                    offset,
+                    len,
+                    ir,
+                    insn,
+                })
+            }
+            /// Decodes bytes instruction after instruction, an undecodable one is skipped by its
+            /// length.
+            fn decode_stream<'a>(&'a self, bytes: &'a [u8]) -> InsnStream<'a, Self>
+            where
+                Self: Sized,
+            {
+                InsnStream {
+                    map: self,
+                    bytes,
+                    offset: 0,
+                }
+            }
         }
 
-        lazy_static! {
-            pub static ref GDECODER: GlobalInsnMap = {
-                let mut map = GlobalInsnMap::new();
-                for r in REGISTERY_INSN {
-                    r(&mut map)
+        /// Attempts at a random word of one instruction before giving up on it.
+        pub const RANDOM_TRIES: usize = 64;
+
+        /// The length in bytes of the instruction starting with parcel, None for reserved lengths.
+        pub fn insn_len(parcel: u16) -> Option<usize> {
+            if parcel & 0x3 != 0x3 {
+                Some(2)
+            } else if parcel & 0x1c != 0x1c {
+                Some(4)
+            } else if parcel & 0x3f == 0x1f {
+                Some(6)
+            } else if parcel & 0x7f == 0x3f {
+                Some(8)
+            } else if (parcel >> 12) & 0x7 != 0x7 {
+                Some(10 + 2 * ((parcel as usize >> 12) & 0x7))
+            } else {
+                None
+            }
+        }
+
+        pub struct DecodedInsn<'a> {
+            pub offset: usize,
+            pub len: usize,
+            pub ir: $inst,
+            pub insn: &'a Instruction,
+        }
+
+        pub struct InsnStream<'a, M: InsnMap> {
+            map: &'a M,
+            bytes: &'a [u8],
+            offset: usize,
+        }
+
+        impl<'a, M: InsnMap> Iterator for InsnStream<'a, M> {
+            type Item = Result<DecodedInsn<'a>, Error>;
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.offset >= self.bytes.len() {
+                    return None;
                 }
+                let result = self.map.decode_at(self.bytes, self.offset);
+                self.offset = match result {
+                    Ok(ref insn) => self.offset + insn.len,
+                    Err(_) => match self
+                        .bytes
+                        .get(self.offset..self.offset + 2)
+                        .and_then(|p| insn_len(p[0] as u16 | (p[1] as u16) << 8))
+                    {
+                        Some(len) if self.offset + len <= self.bytes.len() => self.offset + len,
+                        _ => self.bytes.len(),
+                    },
+                };
+                Some(result)
+            }
+        }
+
+        /// Builds a `GlobalInsnMap` of the registered instructions enabled for an XLEN and a set of
+        /// extensions, e.g. `DecoderBuilder::new().xlen(32).extensions(&["I", "M"]).build()`.
+        pub struct DecoderBuilder {
+            extensions: Option<Vec<String>>,
+            xlen: usize,
+        }
+
+        impl DecoderBuilder {
+            pub fn new() -> DecoderBuilder {
+                DecoderBuilder {
+                    extensions: None,
+                    xlen: 0,
+                }
+            }
+
+            /// Only instructions of this XLEN or not tagged with any XLEN are registered.
+            pub fn xlen(mut self, xlen: usize) -> DecoderBuilder {
+                self.xlen = xlen;
+                self
+            }
+
+            /// Only instructions tagged with one of these extensions or not tagged at all are registered.
+            pub fn extensions(mut self, extensions: &[&str]) -> DecoderBuilder {
+                self.extensions = Some(extensions.iter().map(|e| e.to_string()).collect());
+                self
+            }
+
+            fn xlen_enabled(&self, decoder: &dyn Decoder) -> bool {
+                self.xlen == 0 || decoder.xlen() == 0 || decoder.xlen() == self.xlen
+            }
+
+            fn enabled(&self, decoder: &dyn Decoder) -> bool {
+                let extension = decoder.extension();
+                self.xlen_enabled(decoder)
+                    && (extension.is_empty()
+                    || self.extensions.as_ref().map_or(true, |exts| {
+                        exts.iter().any(|e| e.eq_ignore_ascii_case(extension))
+                    }))
+            }
+
+            pub fn build(self) -> GlobalInsnMap {
+                let mut map = GlobalInsnMap::new();
+                map.registery_linked(|d| self.enabled(d));
+                //instructions of another XLEN do not exist for the map rather than being disabled
+                map.disable_linked(|d| self.xlen_enabled(d) && !self.enabled(d));
                 map.lock();
                 map
-            };
+            }
+        }
+
+        lazy_static! {
+            /// The map of every linked instruction.
+            pub static ref GDECODER: GlobalInsnMap = DecoderBuilder::new().build();
         }
 
+        /// Every `#[derive(Instruction)]` registers its decoder here.
         #[distributed_slice]
-        pub static REGISTERY_INSN: [fn(&mut GlobalInsnMap)] = [..];
+        pub static REGISTERY_INSN: [fn() -> Box<dyn Decoder>] = [..];
     };
 }
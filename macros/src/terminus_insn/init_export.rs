@@ -0,0 +1,173 @@
+#[macro_export(local_inner_macros)]
+macro_rules! init_export {
+    ($inst:ty) => {
+        impl InsnInfo {
+            /// riscv-opcodes style encoding, msb first with "-" for every variable bit.
+            pub fn encoding(&self) -> String {
+                let bits = (self.len << 3).min(std::mem::size_of::<$inst>() << 3);
+                (0..bits)
+                    .rev()
+                    .map(|i| {
+                        if (self.mask >> i) & 1 == 0 {
+                            '-'
+                        } else if (self.code >> i) & 1 == 1 {
+                            '1'
+                        } else {
+                            '0'
+                        }
+                    })
+                    .collect()
+            }
+
+            /// riscv-opcodes style extension tags, e.g. "rv_m" or "rv64_i", or just the XLEN like
+            /// "rv64" for an instruction only tagged with `#[xlen]`.
+            pub fn extensions(&self) -> Vec<String> {
+                let mut extensions = Vec::new();
+                let xlen = if self.xlen == 0 {
+                    String::new()
+                } else {
+                    self.xlen.to_string()
+                };
+                if !self.extension.is_empty() {
+                    extensions.push(__terminus_insn_format!(
+                        "rv{}_{}",
+                        xlen,
+                        self.extension.to_lowercase()
+                    ));
+                } else if self.xlen != 0 {
+                    extensions.push(__terminus_insn_format!("rv{}", xlen));
+                }
+                extensions
+            }
+        }
+
+        /// The instructions of a map keyed like riscv-opcodes' `instr_dict`, "c.addi" becomes
+        /// "c_addi", exported by `to_json` and `to_yaml`. Instructions sharing a mnemonic and an
+        /// encoding, e.g. per-XLEN variants, make one entry with their extensions combined, the other
+        /// ones sharing a mnemonic are told apart by a "_rv32"-like suffix, then by a counter.
+        pub struct InstrDict(Vec<(String, Vec<InsnInfo>)>);
+
+        impl InstrDict {
+            pub fn new<M: InsnMap>(map: &M) -> InstrDict {
+                let mut infos = map
+                    .iter()
+                    .map(|d| {
+                        let asm = d.decode().asm();
+                        let mnemonic = asm.split_whitespace().next().unwrap_or("");
+                        (mnemonic.to_lowercase().replace('.', "_"), d.info())
+                    })
+                    .collect::<Vec<_>>();
+                infos.sort_by(|a, b| (&a.0, a.1.xlen, &a.1.name).cmp(&(&b.0, b.1.xlen, &b.1.name)));
+                let mut groups: Vec<(String, Vec<InsnInfo>)> = Vec::new();
+                for (key, info) in infos {
+                    match groups
+                        .iter_mut()
+                        .find(|(k, g)| *k == key && (g[0].code, g[0].mask) == (info.code, info.mask))
+                    {
+                        Some((_, group)) => group.push(info),
+                        None => groups.push((key, std::iter::once(info).collect())),
+                    }
+                }
+                let mut entries: Vec<(String, Vec<InsnInfo>)> = Vec::new();
+                for (i, (key, group)) in groups.iter().enumerate() {
+                    let shared = groups.iter().filter(|(k, _)| k == key).count() > 1;
+                    let mut key = match group[0].xlen {
+                        xlen if shared && xlen != 0 && group.iter().all(|info| info.xlen == xlen) => {
+                            __terminus_insn_format!("{}_rv{}", key, xlen)
+                        }
+                        _ => key.clone(),
+                    };
+                    let base = key.clone();
+                    let mut n = 1;
+                    while entries.iter().any(|(k, _)| *k == key)
+                        || groups[i + 1..].iter().any(|(k, _)| *k == key)
+                    {
+                        n += 1;
+                        key = __terminus_insn_format!("{}_{}", base, n);
+                    }
+                    entries.push((key, group.clone()));
+                }
+                InstrDict(entries)
+            }
+
+            /// Every entry with the instructions merged into it.
+            pub fn entries(&self) -> &[(String, Vec<InsnInfo>)] {
+                &self.0
+            }
+
+            fn extensions(infos: &[InsnInfo]) -> Vec<String> {
+                let mut extensions = Vec::new();
+                for extension in infos.iter().flat_map(|info| info.extensions()) {
+                    if !extensions.contains(&extension) {
+                        extensions.push(extension)
+                    }
+                }
+                extensions
+            }
+
+            pub fn to_json(&self) -> String {
+                let quote = |s: &str| {
+                    __terminus_insn_format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+                };
+                let list = |items: Vec<String>| {
+                    __terminus_insn_format!(
+                        "[{}]",
+                        items.iter().map(|s| quote(s)).collect::<Vec<_>>().join(", ")
+                    )
+                };
+                let mut json = String::from("{");
+                for (i, (key, infos)) in self.0.iter().enumerate() {
+                    let info = &infos[0];
+                    if i != 0 {
+                        json.push(',');
+                    }
+                    json.push_str(&__terminus_insn_format!(
+                        "\n  {}: {{\n    \"encoding\": {},\n    \"variable_fields\": {},\n    \"extension\": {},\n    \"match\": \"{:#x}\",\n    \"mask\": \"{:#x}\",\n    \"format\": {}",
+                        quote(key),
+                        quote(&info.encoding()),
+                        list(info.fields.iter().map(|f| f.to_string()).collect()),
+                        list(InstrDict::extensions(infos)),
+                        info.code,
+                        info.mask,
+                        quote(info.format)
+                    ));
+                    if !info.imm.is_empty() {
+                        json.push_str(&__terminus_insn_format!(",\n    \"imm\": {}", quote(info.imm)));
+                    }
+                    json.push_str("\n  }");
+                }
+                json.push_str("\n}\n");
+                json
+            }
+
+            /// Every string scalar is single quoted.
+            pub fn to_yaml(&self) -> String {
+                let quote = |s: &str| __terminus_insn_format!("'{}'", s.replace('\'', "''"));
+                let list = |items: Vec<String>| {
+                    __terminus_insn_format!(
+                        "[{}]",
+                        items.iter().map(|s| quote(s)).collect::<Vec<_>>().join(", ")
+                    )
+                };
+                let mut yaml = String::new();
+                for (key, infos) in self.0.iter() {
+                    let info = &infos[0];
+                    yaml.push_str(&__terminus_insn_format!(
+                        "{}:\n  encoding: {}\n  variable_fields: {}\n  extension: {}\n  match: '{:#x}'\n  mask: '{:#x}'\n  format: {}\n",
+                        quote(key),
+                        quote(&info.encoding()),
+                        list(info.fields.iter().map(|f| f.to_string()).collect()),
+                        list(InstrDict::extensions(infos)),
+                        info.code,
+                        info.mask,
+                        quote(info.format)
+                    ));
+                    if !info.imm.is_empty() {
+                        yaml.push_str(&__terminus_insn_format!("  imm: {}\n", quote(info.imm)));
+                    }
+                }
+                yaml
+            }
+        }
+    };
+}
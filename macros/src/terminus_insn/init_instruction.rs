@@ -20,13 +20,140 @@ macro_rules! init_instruction {
             fn imm_len(&self) -> usize {
                 0
             }
+            /// Whether imm is zero extended instead of sign extended from its `imm_len` bits, the
+            /// immediates of the built-in formats are signed.
+            fn imm_unsigned(&self) -> bool {
+                false
+            }
+            /// The low bits of imm that are always 0 and left out of the assembly operand, the
+            /// operand of U format is the upper 20 bits.
+            fn imm_shift(&self) -> usize {
+                0
+            }
+            fn encode(&self, _rd: $inst, _rs1: $inst, _rs2: $inst, _imm: $inst) -> $inst {
+                0
+            }
+            fn fields(&self) -> &'static [&'static str] {
+                &[]
+            }
+        }
+
+        /// The formats of `define_format!`, which `#[format(..)]` accepts besides the built-in ones.
+        #[diagnostic::on_unimplemented(
+            message = "`{Self}` is neither a built-in format nor a `define_format!` type",
+            label = "not a format"
+        )]
+        pub trait DefinedFormat: Format {
+            const FORMAT: Self;
+        }
+
+        /// The class given by `#[class]` or defaulted from the format.
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        pub enum InsnClass {
+            Other,
+            Branch,
+            Jump,
+            Call,
+            Return,
+            Load,
+            Store,
+            Amo,
+            Csr,
+            Fence,
+            System,
+        }
+
+        /// An integer, FP or vector register, or a csr by number.
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        pub enum Reg {
+            X(u8),
+            F(u8),
+            V(u8),
+            Csr(u16),
+        }
+
+        /// The registers an instruction reads and writes, see `InstructionImp::operands`.
+        #[derive(Debug, Clone, Default, Eq, PartialEq)]
+        pub struct Operands {
+            pub reads: Vec<Reg>,
+            pub writes: Vec<Reg>,
         }
 
         pub trait Execution {
             fn execute(&self, p: &mut $processor) -> Result<(), $exception>;
         }
 
-        pub trait InstructionImp: Format + Execution + Send + Sync {}
+        pub trait InstructionImp: Format + Execution + Send + Sync {
+            fn disasm(&self, code: &$inst) -> String;
+            fn asm(&self) -> &'static str;
+            /// A new instance with its operand fields filled from code.
+            fn decode_owned(&self, code: &$inst) -> Box<dyn InstructionImp>;
+            fn name(&self) -> &'static str {
+                self.asm().split_whitespace().next().unwrap_or("")
+            }
+            fn class(&self) -> InsnClass {
+                InsnClass::Other
+            }
+            /// Integer registers of the fields the format really has, rd is written and rs1/rs2 are
+            /// read, `#[reads]`/`#[writes]` replace them.
+            fn operands(&self, code: &$inst) -> Operands {
+                let mut operands = Operands::default();
+                for field in self.fields() {
+                    match *field {
+                        "rd" => operands.writes.push(Reg::X(self.rd(code) as u8)),
+                        "rs1" => operands.reads.push(Reg::X(self.rs1(code) as u8)),
+                        "rs2" => operands.reads.push(Reg::X(self.rs2(code) as u8)),
+                        _ => {}
+                    }
+                }
+                operands
+            }
+            /// The real immediate given by the `#[imm]` layout, zero-extended.
+            fn imm_value(&self, code: &$inst) -> $inst {
+                self.imm(code)
+            }
+            fn imm_width(&self) -> usize {
+                if self.imm_unsigned() {
+                    0
+                } else {
+                    self.imm_len()
+                }
+            }
+            /// The real immediate sign-extended from `imm_width` bits.
+            fn imm_sext(&self, code: &$inst) -> $inst {
+                let value = self.imm_value(code);
+                let width = self.imm_width();
+                if width == 0 || width >= std::mem::size_of::<$inst>() << 3 {
+                    value
+                } else if (value >> (width - 1)) & 1 == 1 {
+                    value | !(((1 as $inst) << width) - 1)
+                } else {
+                    value
+                }
+            }
+            /// The real immediate as a number, negative only if the immediate is signed.
+            fn imm_signed(&self, code: &$inst) -> i128 {
+                let width = self.imm_width() as u32;
+                let value = self.imm_value(code) as i128;
+                if width == 0 || width >= 128 {
+                    return value;
+                }
+                //imm may already be sign extended past its width
+                let value = value & ((1i128 << width) - 1);
+                if (value >> (width - 1)) & 1 == 1 {
+                    value - (1i128 << width)
+                } else {
+                    value
+                }
+            }
+            fn imm_field(&self, value: $inst) -> $inst {
+                value
+            }
+            /// The "msb[:lsb]|..." layout given by `#[imm]`, "" if the immediate is not scrambled.
+            fn imm_layout(&self) -> &'static str {
+                ""
+            }
+        }
 
         pub struct Instruction(Box<dyn InstructionImp>);
 
@@ -1,27 +1,45 @@
 #[macro_export(local_inner_macros)]
 macro_rules! init_simplemap {
     ($inst:ty) => {
-        pub type GlobalInsnMap = SimpleInsnMap;
-        pub struct SimpleInsnMap(std::collections::HashMap<InsnT, Box<dyn Decoder>>);
+        /// A map scanning its decoders in order. They are kept sorted by precedence, so the first
+        /// matched one wins.
+        pub struct SimpleInsnMap(Vec<Box<dyn Decoder>>, DecodeContext);
 
         impl SimpleInsnMap {
             pub fn new() -> SimpleInsnMap {
-                SimpleInsnMap(std::collections::HashMap::new())
+                SimpleInsnMap(Vec::new(), DecodeContext::default())
             }
         }
 
         impl InsnMap for SimpleInsnMap {
             fn registery<T: 'static + Decoder>(&mut self, decoder: T) {
-                self.0.insert(decoder.code(), Box::new(decoder));
+                if let Some(v) = self.0.iter().find(|v| v.code() == decoder.code() && v.mask() == decoder.mask() && decoder.xlen_compatible(v.as_ref())) {
+                    __terminus_insn_panic!("inst {}(code = {:#x}; mask = {:#x}) is duplicated with inst {}(code = {:#x}; mask = {:#x})!", decoder.name(), decoder.code(), decoder.mask(), v.name(), v.code(), v.mask())
+                }
+                let ties = self.iter().filter_map(|v| Conflict::xlen_tie(&decoder, v)).collect::<Vec<_>>();
+                self.1.ties.extend(ties);
+                let pos = self
+                    .0
+                    .iter()
+                    .position(|v| decoder.precedence(v.as_ref()) == std::cmp::Ordering::Less)
+                    .unwrap_or(self.0.len());
+                self.0.insert(pos, Box::new(decoder));
             }
 
             fn decode(&self, ir: &$inst) -> Result<&Instruction, Error> {
-                let decoder = self.0.values().find(|d| d.matched(ir));
-                if let Some(d) = decoder {
-                    Ok(d.decode())
-                } else {
-                    Err(Error::Illegal(*ir))
-                }
+                self.1.result(self.0.iter().find(|d| d.matched(ir)).map(|d| d.as_ref()), ir)
+            }
+
+            fn lock(&mut self) {
+                self.1.lock();
+            }
+
+            fn disable<T: 'static + Decoder>(&mut self, decoder: T) {
+                self.1.disable(decoder)
+            }
+
+            fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a dyn Decoder> + 'a> {
+                Box::new(self.0.iter().map(|d| d.as_ref()))
             }
         }
     };
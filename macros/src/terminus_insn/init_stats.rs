@@ -0,0 +1,44 @@
+#[macro_export(local_inner_macros)]
+macro_rules! init_stats {
+    ($inst:ty) => {
+        __terminus_insn_stats! {
+            fn record_stats(decoder: &dyn Decoder) {
+                if let Some(hits) = decoder.hits() {
+                    hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+
+            /// Decode hits of the instructions of a map, every map counts its own.
+            pub trait InsnStats: InsnMap {
+                /// Decode hits of every instruction in the map, most frequent first.
+                fn stats(&self) -> Vec<(String, u64)> {
+                    let mut stats = self
+                        .iter()
+                        .filter_map(|d| d.hits().map(|hits| (d.name(), hits.load(std::sync::atomic::Ordering::Relaxed))))
+                        .collect::<Vec<_>>();
+                    stats.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                    stats
+                }
+
+                /// Clears the counts of `stats`.
+                fn reset_stats(&self) {
+                    for hits in self.iter().filter_map(|d| d.hits()) {
+                        hits.store(0, std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
+            }
+
+            impl<M: InsnMap> InsnStats for M {}
+
+            /// Decode hits of every instruction in `GDECODER`, most frequent first.
+            pub fn stats() -> Vec<(String, u64)> {
+                GDECODER.stats()
+            }
+
+            /// Clears the counts of `stats`.
+            pub fn reset_stats() {
+                GDECODER.reset_stats()
+            }
+        }
+    };
+}
@@ -0,0 +1,319 @@
+#[macro_export(local_inner_macros)]
+macro_rules! init_trace {
+    ($processor:ident, $exception:ident, $inst:ty) => {
+        /// The instruction a trace hook is called for.
+        pub struct TraceEvent<'a> {
+            pub pc: u64,
+            pub ir: $inst,
+            pub name: &'static str,
+            pub insn: &'a Instruction,
+        }
+
+        type PreHook<'a> = Box<dyn FnMut(&$processor, &TraceEvent) + 'a>;
+        type PostHook<'a> = Box<dyn FnMut(&$processor, &TraceEvent, &Result<(), $exception>) + 'a>;
+
+        /// Runs `Execution::execute` between the registered pre and post hooks.
+        pub struct Tracer<'a> {
+            pre: Vec<PreHook<'a>>,
+            post: Vec<PostHook<'a>>,
+        }
+
+        impl<'a> Tracer<'a> {
+            pub fn new() -> Tracer<'a> {
+                Tracer {
+                    pre: Vec::new(),
+                    post: Vec::new(),
+                }
+            }
+
+            pub fn pre<F: FnMut(&$processor, &TraceEvent) + 'a>(&mut self, f: F) -> &mut Self {
+                self.pre.push(Box::new(f));
+                self
+            }
+
+            pub fn post<F: FnMut(&$processor, &TraceEvent, &Result<(), $exception>) + 'a>(
+                &mut self,
+                f: F,
+            ) -> &mut Self {
+                self.post.push(Box::new(f));
+                self
+            }
+
+            pub fn execute(
+                &mut self,
+                insn: &Instruction,
+                p: &mut $processor,
+                pc: u64,
+                ir: $inst,
+            ) -> Result<(), $exception> {
+                let event = TraceEvent {
+                    pc,
+                    ir,
+                    name: insn.name(),
+                    insn,
+                };
+                for hook in self.pre.iter_mut() {
+                    hook(p, &event)
+                }
+                let result = insn.execute(p);
+                for hook in self.post.iter_mut() {
+                    hook(p, &event, &result)
+                }
+                result
+            }
+        }
+
+        /// A memory access of a traced instruction, printed as a `mem` record by `SpikeCommitLog`.
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        pub enum MemAccess {
+            Load(u64),
+            /// address, value and size in bytes
+            Store(u64, u64, usize),
+        }
+
+        /// Formats lines like spike `--log-commits`: register writes, csrs named as "c768_mstatus",
+        /// then a `mem` record for every memory access.
+        pub struct SpikeCommitLog {
+            pub hart: usize,
+            pub xlen: usize,
+        }
+
+        impl SpikeCommitLog {
+            pub fn new(hart: usize, xlen: usize) -> SpikeCommitLog {
+                SpikeCommitLog { hart, xlen }
+            }
+
+            /// The name spike gives csr, "unknown-csr" for numbers it does not declare.
+            pub fn csr_name(csr: u16) -> &'static str {
+                match csr {
+                    0x001 => "fflags",
+                    0x002 => "frm",
+                    0x003 => "fcsr",
+                    0x008 => "vstart",
+                    0x009 => "vxsat",
+                    0x00a => "vxrm",
+                    0x00f => "vcsr",
+                    0x015 => "seed",
+                    0xc00 => "cycle",
+                    0xc01 => "time",
+                    0xc02 => "instret",
+                    0xc03..=0xc1f => [
+                        "hpmcounter3",
+                        "hpmcounter4",
+                        "hpmcounter5",
+                        "hpmcounter6",
+                        "hpmcounter7",
+                        "hpmcounter8",
+                        "hpmcounter9",
+                        "hpmcounter10",
+                        "hpmcounter11",
+                        "hpmcounter12",
+                        "hpmcounter13",
+                        "hpmcounter14",
+                        "hpmcounter15",
+                        "hpmcounter16",
+                        "hpmcounter17",
+                        "hpmcounter18",
+                        "hpmcounter19",
+                        "hpmcounter20",
+                        "hpmcounter21",
+                        "hpmcounter22",
+                        "hpmcounter23",
+                        "hpmcounter24",
+                        "hpmcounter25",
+                        "hpmcounter26",
+                        "hpmcounter27",
+                        "hpmcounter28",
+                        "hpmcounter29",
+                        "hpmcounter30",
+                        "hpmcounter31",
+                    ][csr as usize - 0xc03],
+                    0xc20 => "vl",
+                    0xc21 => "vtype",
+                    0xc22 => "vlenb",
+                    0xc80 => "cycleh",
+                    0xc81 => "timeh",
+                    0xc82 => "instreth",
+                    0x100 => "sstatus",
+                    0x104 => "sie",
+                    0x105 => "stvec",
+                    0x106 => "scounteren",
+                    0x10a => "senvcfg",
+                    0x140 => "sscratch",
+                    0x141 => "sepc",
+                    0x142 => "scause",
+                    0x143 => "stval",
+                    0x144 => "sip",
+                    0x14d => "stimecmp",
+                    0x180 => "satp",
+                    0x200 => "vsstatus",
+                    0x204 => "vsie",
+                    0x205 => "vstvec",
+                    0x240 => "vsscratch",
+                    0x241 => "vsepc",
+                    0x242 => "vscause",
+                    0x243 => "vstval",
+                    0x244 => "vsip",
+                    0x280 => "vsatp",
+                    0x600 => "hstatus",
+                    0x602 => "hedeleg",
+                    0x603 => "hideleg",
+                    0x604 => "hie",
+                    0x606 => "hcounteren",
+                    0x607 => "hgeie",
+                    0x60a => "henvcfg",
+                    0x643 => "htval",
+                    0x644 => "hip",
+                    0x645 => "hvip",
+                    0x64a => "htinst",
+                    0x680 => "hgatp",
+                    0xe12 => "hgeip",
+                    0x300 => "mstatus",
+                    0x301 => "misa",
+                    0x302 => "medeleg",
+                    0x303 => "mideleg",
+                    0x304 => "mie",
+                    0x305 => "mtvec",
+                    0x306 => "mcounteren",
+                    0x30a => "menvcfg",
+                    0x310 => "mstatush",
+                    0x31a => "menvcfgh",
+                    0x320 => "mcountinhibit",
+                    0x340 => "mscratch",
+                    0x341 => "mepc",
+                    0x342 => "mcause",
+                    0x343 => "mtval",
+                    0x344 => "mip",
+                    0x34a => "mtinst",
+                    0x34b => "mtval2",
+                    0x3a0..=0x3af => [
+                        "pmpcfg0", "pmpcfg1", "pmpcfg2", "pmpcfg3", "pmpcfg4", "pmpcfg5",
+                        "pmpcfg6", "pmpcfg7", "pmpcfg8", "pmpcfg9", "pmpcfg10", "pmpcfg11",
+                        "pmpcfg12", "pmpcfg13", "pmpcfg14", "pmpcfg15",
+                    ][csr as usize - 0x3a0],
+                    0x3b0..=0x3bf => [
+                        "pmpaddr0",
+                        "pmpaddr1",
+                        "pmpaddr2",
+                        "pmpaddr3",
+                        "pmpaddr4",
+                        "pmpaddr5",
+                        "pmpaddr6",
+                        "pmpaddr7",
+                        "pmpaddr8",
+                        "pmpaddr9",
+                        "pmpaddr10",
+                        "pmpaddr11",
+                        "pmpaddr12",
+                        "pmpaddr13",
+                        "pmpaddr14",
+                        "pmpaddr15",
+                    ][csr as usize - 0x3b0],
+                    0x747 => "mseccfg",
+                    0x757 => "mseccfgh",
+                    0x7a0 => "tselect",
+                    0x7a1 => "tdata1",
+                    0x7a2 => "tdata2",
+                    0x7a3 => "tdata3",
+                    0x7a4 => "tinfo",
+                    0x7a5 => "tcontrol",
+                    0x7a8 => "mcontext",
+                    0x7b0 => "dcsr",
+                    0x7b1 => "dpc",
+                    0x7b2 => "dscratch0",
+                    0x7b3 => "dscratch1",
+                    0xb00 => "mcycle",
+                    0xb02 => "minstret",
+                    0xb80 => "mcycleh",
+                    0xb82 => "minstreth",
+                    0xf11 => "mvendorid",
+                    0xf12 => "marchid",
+                    0xf13 => "mimpid",
+                    0xf14 => "mhartid",
+                    0xf15 => "mconfigptr",
+                    _ => "unknown-csr",
+                }
+            }
+
+            pub fn line(
+                &self,
+                privilege: u8,
+                event: &TraceEvent,
+                writes: &[(Reg, u64)],
+                mem: &[MemAccess],
+            ) -> String {
+                let hex = |bits: usize, value: u64| {
+                    __terminus_insn_format!("0x{:01$x}", value, bits.div_ceil(4))
+                };
+                let len = insn_len(event.ir as u16).unwrap_or(4);
+                let mut line = __terminus_insn_format!(
+                    "core {:>3}: {} {} ({})",
+                    self.hart,
+                    privilege,
+                    hex(self.xlen, event.pc),
+                    hex(len << 3, event.ir as u64)
+                );
+                for (reg, value) in writes {
+                    let name = match reg {
+                        Reg::X(0) => continue,
+                        Reg::X(i) => __terminus_insn_format!("x{:<2}", i),
+                        Reg::F(i) => __terminus_insn_format!("f{:<2}", i),
+                        Reg::V(i) => __terminus_insn_format!("v{:<2}", i),
+                        Reg::Csr(i) => __terminus_insn_format!("c{}_{}", i, Self::csr_name(*i)),
+                    };
+                    line.push_str(&__terminus_insn_format!(
+                        " {} {}",
+                        name,
+                        hex(self.xlen, *value)
+                    ));
+                }
+                for access in mem {
+                    let record = match *access {
+                        MemAccess::Load(addr) => {
+                            __terminus_insn_format!(" mem {}", hex(self.xlen, addr))
+                        }
+                        MemAccess::Store(addr, value, size) => __terminus_insn_format!(
+                            " mem {} {}",
+                            hex(self.xlen, addr),
+                            hex(size << 3, value)
+                        ),
+                    };
+                    line.push_str(&record);
+                }
+                line
+            }
+
+            /// A post hook writing a line for every instruction executed without exception, with the
+            /// values of the registers it writes read back by read and the memory accesses returned by
+            /// mem, which lists loads before stores as spike does.
+            pub fn hook<'a, W, R, M, P>(
+                self,
+                mut out: W,
+                read: R,
+                mem: M,
+                privilege: P,
+            ) -> impl FnMut(&$processor, &TraceEvent, &Result<(), $exception>) + 'a
+            where
+                W: std::io::Write + 'a,
+                R: Fn(&$processor, Reg) -> u64 + 'a,
+                M: Fn(&$processor, &TraceEvent) -> Vec<MemAccess> + 'a,
+                P: Fn(&$processor) -> u8 + 'a,
+            {
+                move |p, event, result| {
+                    if result.is_ok() {
+                        let writes = event
+                            .insn
+                            .operands(&event.ir)
+                            .writes
+                            .into_iter()
+                            .map(|reg| (reg, read(p, reg)))
+                            .collect::<Vec<_>>();
+                        let mut line = self.line(privilege(p), event, &writes, &mem(p, event));
+                        line.push('\n');
+                        let _ = out.write_all(line.as_bytes());
+                    }
+                }
+            }
+        }
+    };
+}
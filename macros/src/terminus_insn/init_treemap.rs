@@ -1,13 +1,11 @@
 #[macro_export(local_inner_macros)]
 macro_rules! init_treemap {
     ($inst:ty) => {
-        pub type GlobalInsnMap = TreeInsnMap;
-
         struct TreeNode {
-            left: Option<*mut TreeNode>,
-            right: Option<*mut TreeNode>,
+            left: Option<usize>,
+            right: Option<usize>,
             level: usize,
-            value: Option<Box<dyn Decoder>>,
+            values: Vec<Box<dyn Decoder>>,
         }
 
         impl TreeNode {
@@ -16,108 +14,105 @@ macro_rules! init_treemap {
                     left: None,
                     right: None,
                     level: level,
-                    value: None,
+                    values: Vec::new(),
                 }
             }
 
             fn max_level() -> usize { std::mem::size_of::<$inst>() << 3 }
+        }
+
+        /// A map walking a tree keyed by the code bits. Nodes live in an arena, children are
+        /// indices into it and nodes[0] is the root.
+        pub struct TreeInsnMap(Vec<TreeNode>, DecodeContext);
+
+        impl TreeInsnMap {
+            pub fn new() -> TreeInsnMap {
+                let mut nodes = Vec::new();
+                nodes.push(TreeNode::new(0));
+                TreeInsnMap(nodes, DecodeContext::default())
+            }
 
             fn insert(&mut self, value: Box<dyn Decoder>) -> Option<&Box<dyn Decoder>> {
-                if self.level == Self::max_level() {
-                    if let Some(ref v) = self.value {
-                        Some(v)
-                    } else {
-                        self.value = Some(value);
-                        None
-                    }
-                } else {
-                    let node = if value.code() & ((1 as $inst) << self.level as $inst) == 0 {
-                        self.left.get_or_insert(Box::into_raw(Box::new(Self::new(self.level + 1))))
+                let mut node = 0;
+                while self.0[node].level != TreeNode::max_level() {
+                    let level = self.0[node].level;
+                    let right = value.code() & ((1 as $inst) << level as $inst) != 0;
+                    let child = if right { self.0[node].right } else { self.0[node].left };
+                    node = if let Some(child) = child {
+                        child
                     } else {
-                        self.right.get_or_insert(Box::into_raw(Box::new(Self::new(self.level + 1))))
+                        let child = self.0.len();
+                        self.0.push(TreeNode::new(level + 1));
+                        if right {
+                            self.0[node].right = Some(child)
+                        } else {
+                            self.0[node].left = Some(child)
+                        }
+                        child
                     };
-                    unsafe {
-                        (*node).as_mut().unwrap().insert(value)
-                    }
                 }
+                //decoders sharing a code path only differ in mask or xlen, keep them sorted by precedence
+                let leaf = &mut self.0[node].values;
+                if let Some(pos) = leaf
+                    .iter()
+                    .position(|v| v.mask() == value.mask() && v.xlen_compatible(value.as_ref()))
+                {
+                    return Some(&leaf[pos]);
+                }
+                let pos = leaf
+                    .iter()
+                    .position(|v| value.precedence(v.as_ref()) == std::cmp::Ordering::Less)
+                    .unwrap_or(leaf.len());
+                leaf.insert(pos, value);
+                None
             }
 
-            fn get_node(node: *mut Self) -> *mut Self {
-                let n = unsafe {
-                    node.as_mut().unwrap()
-                };
-                if n.left.is_some() && n.right.is_none() {
-                    Self::get_node(n.left.unwrap())
-                } else if n.right.is_some() && n.left.is_none() {
-                    Self::get_node(n.right.unwrap())
-                } else {
-                    node
+            fn get_node(&self, node: usize) -> usize {
+                let n = &self.0[node];
+                match (n.left, n.right) {
+                    (Some(left), None) => self.get_node(left),
+                    (None, Some(right)) => self.get_node(right),
+                    _ => node,
                 }
             }
 
             fn compress(&mut self) {
-                let path_compress = |node: &mut Option<*mut Self>| {
-                    if let Some(ref n) = node {
-                        let new_node = Self::get_node(*n);
-                        if *n != new_node {
-                            *node = Some(new_node)
-                        }
+                for node in 0..self.0.len() {
+                    if self.0[node].level == TreeNode::max_level() {
+                        continue;
                     }
-                };
-                if self.level == Self::max_level() {
-                    return;
-                } else {
-                    path_compress(&mut self.left);
-                    path_compress(&mut self.right);
-                    self.left.iter_mut().for_each(|n| { unsafe { n.as_mut().unwrap().compress() } });
-                    self.right.iter_mut().for_each(|n| { unsafe { n.as_mut().unwrap().compress() } });
+                    let left = self.0[node].left.map(|n| self.get_node(n));
+                    let right = self.0[node].right.map(|n| self.get_node(n));
+                    self.0[node].left = left;
+                    self.0[node].right = right;
                 }
             }
 
-            fn get(&self, key: &$inst) -> Option<&Box<dyn Decoder>> {
-                if self.level ==  Self::max_level() {
-                    if let Some(ref v) = self.value {
-                        if v.mask() & *key == v.code() {
-                            return Some(v)
-                        } else {
-                            return None
+            fn find(&self, ir: &$inst) -> Option<&dyn Decoder> {
+                let mut decoder = None;
+                self.get(0, ir, &mut decoder);
+                decoder
+            }
+
+            fn get<'a>(&'a self, node: usize, key: &$inst, best: &mut Option<&'a dyn Decoder>) {
+                let n = &self.0[node];
+                if n.level == TreeNode::max_level() {
+                    if let Some(v) = n.values.iter().find(|v| v.matched(key)) {
+                        if best.map_or(true, |b| v.precedence(b) == std::cmp::Ordering::Less) {
+                            *best = Some(v.as_ref())
                         }
                     }
-                    __terminus_insn_unreachable!()
                 } else {
-                    if *key & ((1 as $inst) << self.level as $inst) == 0 {
-                        if let Some(n) = self.left {
-                            if let Some(v) = unsafe { n.as_ref().unwrap().get(key) } {
-                                return Some(v);
-                            }
-                        }
-                        if let Some(n) = self.right {
-                            if let Some(v) = unsafe { n.as_ref().unwrap().get(key) } {
-                                return Some(v);
-                            }
-                        }
-                    } else {
-                        if let Some(n) = self.right {
-                            if let Some(v) = unsafe { n.as_ref().unwrap().get(key) } {
-                                return Some(v);
-                            }
-                        }
-                        if let Some(n) = self.left {
-                            if let Some(v) = unsafe { n.as_ref().unwrap().get(key) } {
-                                return Some(v);
-                            }
+                    //a code bit set in the path can never match a key bit cleared
+                    if *key & ((1 as $inst) << n.level as $inst) != 0 {
+                        if let Some(right) = n.right {
+                            self.get(right, key, best)
                         }
                     }
+                    if let Some(left) = n.left {
+                        self.get(left, key, best)
+                    }
                 }
-                None
-            }
-        }
-
-        pub struct TreeInsnMap(TreeNode);
-
-        impl TreeInsnMap {
-            pub fn new() -> TreeInsnMap {
-                TreeInsnMap(TreeNode::new(0))
             }
         }
 
@@ -126,27 +121,26 @@ macro_rules! init_treemap {
                 let name = decoder.name();
                 let code = decoder.code();
                 let mask = decoder.mask();
-                if let Some(v) = self.0.insert(Box::new(decoder)) {
+                let ties = self.iter().filter_map(|v| Conflict::xlen_tie(&decoder, v)).collect::<Vec<_>>();
+                if let Some(v) = self.insert(Box::new(decoder)) {
                     __terminus_insn_panic!("inst {}(code = {:#x}; mask = {:#x}) is duplicated with inst {}(code = {:#x}; mask = {:#x})!", name, code, mask,v.name(), v.code(), v.mask())
                 }
+                self.1.ties.extend(ties);
             }
 
             fn decode(&self, ir: &$inst) -> Result<&Instruction, Error> {
-                if let Some(decoder) = self.0.get(ir) {
-                    Ok(decoder.decode())
-                } else {
-                    Err(Error::Illegal(*ir))
-                }
+                self.1.result(self.find(ir), ir)
+            }
+            fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a dyn Decoder> + 'a> {
+                Box::new(self.0.iter().flat_map(|n| n.values.iter().map(|v| v.as_ref())))
             }
             fn lock(&mut self) {
-                self.0.compress();
+                self.compress();
+                self.1.lock();
+            }
+            fn disable<T: 'static + Decoder>(&mut self, decoder: T) {
+                self.1.disable(decoder)
             }
         }
-
-        //immutable after 'lock'
-        unsafe impl Sync for TreeInsnMap {}
-
-        //immutable after 'lock'
-        unsafe impl Send for TreeInsnMap {}
     };
 }
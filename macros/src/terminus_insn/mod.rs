@@ -1,8 +1,19 @@
+mod init_assembler;
+mod init_cache;
+mod init_check;
 mod init_decoder;
+mod init_export;
 mod init_instruction;
 mod init_simplemap;
+mod init_stats;
+mod init_trace;
 mod init_treemap;
 
+/// Generates the instruction framework for an instruction word type, a processor and an exception
+/// type: `Instruction`, the `InsnMap` trait with `TreeInsnMap` and `SimpleInsnMap`, `GDECODER`,
+/// `DecoderBuilder`, `assemble`, `InstrDict`, `Tracer`, `BlockCache`, `self_check` and, with the
+/// "stats" feature, `InsnStats`. The last argument picks `GlobalInsnMap`: `Tree` by default, `Simple`,
+/// `Static` for the map built by `isa!`, or `USER_DEFINE` to define it yourself.
 #[macro_export]
 macro_rules! terminus_insn {
     ($inst:ty, $processor:ident, $exception:ident) => {
@@ -13,16 +24,27 @@ macro_rules! terminus_insn {
     };
     ($inst:ty, $processor:ident, $exception:ident, Tree) => {
         terminus_insn!(@common $inst, $processor, $exception);
-        init_treemap!(TerminusInsnT);
+        pub type GlobalInsnMap = TreeInsnMap;
+    };
+    ($inst:ty, $processor:ident, $exception:ident, Static) => {
+        terminus_insn!(@common $inst, $processor, $exception);
     };
     ($inst:ty, $processor:ident, $exception:ident, Simple) => {
         terminus_insn!(@common $inst, $processor, $exception);
-        init_simplemap!(TerminusInsnT);
+        pub type GlobalInsnMap = SimpleInsnMap;
     };
     (@common $inst:ty, $processor:ident, $exception:ident) => {
         pub type TerminusInsnT = $inst;
         init_instruction!($processor, $exception, TerminusInsnT);
         init_decoder!(TerminusInsnT);
+        init_assembler!(TerminusInsnT);
+        init_export!(TerminusInsnT);
+        init_trace!($processor, $exception, TerminusInsnT);
+        init_cache!(TerminusInsnT);
+        init_stats!(TerminusInsnT);
+        init_treemap!(TerminusInsnT);
+        init_simplemap!(TerminusInsnT);
+        init_check!(TerminusInsnT);
     };
 }
 
@@ -49,3 +71,28 @@ macro_rules! __terminus_insn_format {
         format!($($s)*)
     };
 }
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __terminus_insn_eprintln {
+    ($($s:tt)*) => {
+        eprintln!($($s)*)
+    };
+}
+
+//expands its input only when the "stats" feature of this crate is enabled
+#[cfg(feature = "stats")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __terminus_insn_stats {
+    ($($s:tt)*) => {
+        $($s)*
+    };
+}
+
+#[cfg(not(feature = "stats"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __terminus_insn_stats {
+    ($($s:tt)*) => {};
+}
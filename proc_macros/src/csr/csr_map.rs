@@ -25,11 +25,7 @@ impl Parse for CsrMaps {
         if low.base10_parse::<usize>()? > high.base10_parse::<usize>()? {
             return Err(Error::new(
                 range.span(),
-                format!(
-                    "low {} is bigger than high {} !",
-                    low.to_string(),
-                    high.to_string()
-                ),
+                format!("low {} is bigger than high {} !", low, high),
             ));
         }
         let csrs: ParseBuffer;
@@ -114,7 +110,7 @@ impl<'a> Maps<'a> {
                 csr_map.addr.span(),
                 format!(
                     "addr of {}(0x{:x}) is out of range(0x{:x}, 0x{:x})!",
-                    csr_map.name.to_string(),
+                    csr_map.name,
                     csr_map.addr_value(),
                     self.low,
                     self.high
@@ -125,7 +121,7 @@ impl<'a> Maps<'a> {
                 if csr_map.same_name(prev) {
                     return Err(Error::new(
                         csr_map.name.span(),
-                        format!("map name {} is redefined!", csr_map.name.to_string()),
+                        format!("map name {} is redefined!", csr_map.name),
                     ));
                 }
                 if csr_map.same_addr(prev) {
@@ -133,9 +129,9 @@ impl<'a> Maps<'a> {
                         csr_map.addr.span(),
                         format!(
                             "addr of {}(0x{:x}) is overlapped with {}!",
-                            csr_map.name.to_string(),
+                            csr_map.name,
                             csr_map.addr_value(),
-                            prev.name.to_string()
+                            prev.name
                         ),
                     ));
                 }
@@ -156,7 +152,7 @@ impl<'a> Maps<'a> {
         };
         let fields = quote_map_fold(self.maps.iter(), |csr_map| {
             let name = &csr_map.name;
-            let ty = ty_trans(&csr_map);
+            let ty = ty_trans(csr_map);
             if locked {
                 quote! {#name:std::sync::Mutex<#ty>,}
             } else {
@@ -165,7 +161,7 @@ impl<'a> Maps<'a> {
         });
         let fields_access = quote_map_fold(self.maps.iter(), |csr_map| {
             let name = &csr_map.name;
-            let ty = ty_trans(&csr_map);
+            let ty = ty_trans(csr_map);
             let mut_name = format_ident!("{}_mut", name);
             let immut_access = if locked {
                 quote! {
@@ -200,7 +196,7 @@ impl<'a> Maps<'a> {
         });
         let new_fn = quote_map_fold(self.maps.iter(), |csr_map| {
             let name = &csr_map.name;
-            let ty = ty_trans(&csr_map);
+            let ty = ty_trans(csr_map);
             if locked {
                 quote! {#name:std::sync::Mutex::new(#ty::new(xlen, 0)),}
             } else {
@@ -129,11 +129,7 @@ impl Parse for Field {
         if msb.base10_parse::<usize>()? < lsb.base10_parse::<usize>()? {
             return Err(Error::new(
                 msb.span(),
-                format!(
-                    "msb {} is smaller than lsb {} !",
-                    msb.to_string(),
-                    lsb.to_string()
-                ),
+                format!("msb {} is smaller than lsb {} !", msb, lsb),
             ));
         }
 
@@ -170,18 +166,14 @@ impl<'a> Fields<'a> {
         if self.overflow(field) {
             Err(Error::new(
                 field.name.span(),
-                format!(
-                    "field {}{:?} overflow!",
-                    field.name.to_string(),
-                    field.range()
-                ),
+                format!("field {}{:?} overflow!", field.name, field.range()),
             ))
         } else {
             for prev in self.fields.iter() {
                 if field.same_name(prev) {
                     return Err(Error::new(
                         field.name.span(),
-                        format!("field {} is redefined!", field.name.to_string()),
+                        format!("field {} is redefined!", field.name),
                     ));
                 }
                 if field.overlap(prev) {
@@ -189,9 +181,9 @@ impl<'a> Fields<'a> {
                         field.name.span(),
                         format!(
                             "field {}{:?} is overlapped with field {}{:?}!",
-                            field.name.to_string(),
+                            field.name,
                             field.range(),
-                            prev.name.to_string(),
+                            prev.name,
                             prev.range()
                         ),
                     ));
@@ -388,13 +380,11 @@ impl<'a> FieldSet<'a> {
             );
             let getter_msg = format!(
                 "{} not implement {} in current xlen setting!",
-                self.name.to_string(),
-                getter.to_string()
+                self.name, getter
             );
             let setter_msg = format!(
                 "{} not implement {} in current xlen setting!",
-                self.name.to_string(),
-                setter.to_string()
+                self.name, setter
             );
             quote! {
                 fn #getter_with_trans(&self, t:&#transforms_name) -> u64 { panic!(#getter_msg)}
@@ -37,6 +37,7 @@ mod privilege_kw {
     syn::custom_keyword!(RW);
 }
 
+#[allow(dead_code)]
 #[derive(Debug)]
 enum CsrPrivilege {
     RO(privilege_kw::RO),
@@ -0,0 +1,448 @@
+use proc_macro2::{Span, TokenStream};
+use regex::Regex;
+use std::collections::HashMap;
+use syn::parse::{Error, Parse, ParseStream, Result};
+use syn::{Ident, LitInt, LitStr, Token};
+
+pub struct DecodeTree {
+    file: LitStr,
+    width: usize,
+}
+
+impl Parse for DecodeTree {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let file = input.parse::<LitStr>()?;
+        let width = if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let width = input.parse::<LitInt>()?;
+            let value = width.base10_parse::<usize>()?;
+            if value == 0 || value > 128 {
+                return Err(Error::new(
+                    width.span(),
+                    format!("invalid insn width {}!", value),
+                ));
+            }
+            value
+        } else {
+            32
+        };
+        input.parse::<Option<Token![,]>>()?;
+        Ok(DecodeTree { file, width })
+    }
+}
+
+//segments are msb first as (pos, len), the first one decides the sign
+#[derive(Clone)]
+struct Field {
+    segments: Vec<(usize, usize)>,
+    signed: bool,
+    function: Option<String>,
+}
+
+#[derive(Clone)]
+enum Arg {
+    Field(Field),
+    Const(u128),
+}
+
+#[derive(Clone)]
+struct Format {
+    bits: Vec<char>,
+    args: HashMap<String, Arg>,
+}
+
+struct Pattern {
+    name: String,
+    bits: Vec<char>,
+    args: HashMap<String, Arg>,
+    priority: i32,
+}
+
+struct Parser {
+    width: usize,
+    fields: HashMap<String, Field>,
+    formats: HashMap<String, Format>,
+    patterns: Vec<Pattern>,
+    //true for an overlap group "{", false for a "[" group
+    groups: Vec<bool>,
+}
+
+pub fn expand(tree: DecodeTree) -> Result<TokenStream> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    let path = std::path::Path::new(&dir).join(tree.file.value());
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+        Error::new(
+            tree.file.span(),
+            format!("can not read \"{}\": {}!", path.display(), e),
+        )
+    })?;
+    let mut parser = Parser {
+        width: tree.width,
+        fields: HashMap::new(),
+        formats: HashMap::new(),
+        patterns: vec![],
+        groups: vec![],
+    };
+    let mut line = String::new();
+    let mut start = 0;
+    for (i, raw) in content.lines().enumerate() {
+        let raw = raw.split('#').next().unwrap_or("").trim_end();
+        if line.is_empty() {
+            start = i + 1;
+        }
+        if let Some(raw) = raw.strip_suffix('\\') {
+            line.push_str(raw);
+            line.push(' ');
+            continue;
+        }
+        line.push_str(raw);
+        parser.parse_line(line.trim()).map_err(|msg| {
+            Error::new(
+                tree.file.span(),
+                format!("{}:{}: {}", tree.file.value(), start, msg),
+            )
+        })?;
+        line.clear();
+    }
+    if !parser.groups.is_empty() {
+        return Err(Error::new(
+            tree.file.span(),
+            format!("{}: group is not closed!", tree.file.value()),
+        ));
+    }
+    let count = parser.patterns.len() as i32;
+    for pattern in parser.patterns.iter_mut().filter(|p| p.priority != 0) {
+        pattern.priority = count + 1 - pattern.priority;
+    }
+    let path = path.to_string_lossy().to_string();
+    let patterns = parser
+        .patterns
+        .iter()
+        .map(|p| expand_pattern(p, tree.width))
+        .collect::<Vec<_>>();
+    Ok(quote!(
+        const _: &str = include_str!(#path);
+        #(#patterns)*
+    ))
+}
+
+impl Parser {
+    fn parse_line(&mut self, line: &str) -> std::result::Result<(), String> {
+        if line.is_empty() {
+            return Ok(());
+        }
+        if line == "{" || line == "[" {
+            self.groups.push(line == "{");
+            return Ok(());
+        }
+        if line == "}" || line == "]" {
+            return match self.groups.pop() {
+                Some(overlap) if overlap == (line == "}") => Ok(()),
+                _ => Err(format!("unexpected \"{}\"!", line)),
+            };
+        }
+        let mut tokens = line.split_whitespace();
+        let head = tokens.next().unwrap_or("");
+        let tokens = tokens.collect::<Vec<_>>();
+        if let Some(name) = head.strip_prefix('%') {
+            let field = self.parse_field(&tokens)?;
+            self.fields.insert(name.to_string(), field);
+        } else if head.starts_with('&') {
+            //argument sets only name the fields of qemu's arg structs, nothing to generate
+        } else if let Some(name) = head.strip_prefix('@') {
+            let format = self.parse_format(&tokens)?;
+            self.formats.insert(name.to_string(), format);
+        } else {
+            //every pattern becomes a struct named after it
+            let name = crate::opcodes::struct_name(head);
+            if self
+                .patterns
+                .iter()
+                .any(|p| crate::opcodes::struct_name(&p.name) == name)
+            {
+                return Err(format!("pattern \"{}\" is defined more than once!", head));
+            }
+            let format = self.parse_format(&tokens)?;
+            let priority = self.priority();
+            self.patterns.push(Pattern {
+                name: head.to_string(),
+                bits: format.bits,
+                args: format.args,
+                priority,
+            });
+        }
+        Ok(())
+    }
+
+    //patterns of overlap groups are numbered in order here and turned into priorities once the
+    //file is parsed, so that earlier patterns win
+    fn priority(&self) -> i32 {
+        if self.groups.iter().any(|overlap| *overlap) {
+            self.patterns.len() as i32 + 1
+        } else {
+            0
+        }
+    }
+
+    fn parse_field(&self, tokens: &[&str]) -> std::result::Result<Field, String> {
+        lazy_static! {
+            static ref SEGMENT: Regex = Regex::new("^([0-9]+):(s?)([0-9]+)$").unwrap();
+        }
+        let mut field = Field {
+            segments: vec![],
+            signed: false,
+            function: None,
+        };
+        for token in tokens {
+            if let Some(function) = token.strip_prefix("!function=") {
+                field.function = Some(function.to_string());
+            } else if let Some(caps) = SEGMENT.captures(token) {
+                let pos = caps[1].parse::<usize>().map_err(|e| e.to_string())?;
+                let len = caps[3].parse::<usize>().map_err(|e| e.to_string())?;
+                if len == 0 || pos + len > self.width {
+                    return Err(format!("invalid field segment \"{}\"!", token));
+                }
+                if field.segments.is_empty() {
+                    field.signed = !caps[2].is_empty();
+                }
+                field.segments.push((pos, len));
+            } else {
+                return Err(format!("invalid field segment \"{}\"!", token));
+            }
+        }
+        Ok(field)
+    }
+
+    fn parse_format(&self, tokens: &[&str]) -> std::result::Result<Format, String> {
+        lazy_static! {
+            static ref INLINE: Regex =
+                Regex::new("^([a-zA-Z_][a-zA-Z0-9_]*):(s?)([0-9]+)$").unwrap();
+            static ref BITS: Regex = Regex::new("^[01.\\-]+$").unwrap();
+        }
+        let mut bits = vec![];
+        let mut args = HashMap::new();
+        let mut inherited: Option<Format> = None;
+        for token in tokens {
+            if let Some(name) = token.strip_prefix('@') {
+                if inherited.is_some() {
+                    return Err("more than one format!".to_string());
+                }
+                inherited = Some(
+                    self.formats
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| format!("undefined format \"@{}\"!", name))?,
+                );
+            } else if token.starts_with('&') {
+                continue;
+            } else if let Some(name) = token.strip_prefix('%') {
+                args.insert(name.to_string(), Arg::Field(self.field(name)?));
+            } else if let Some(pos) = token.find('=') {
+                let (name, value) = (&token[..pos], &token[pos + 1..]);
+                let arg = if let Some(field) = value.strip_prefix('%') {
+                    Arg::Field(self.field(field)?)
+                } else if let Some(hex) = value.strip_prefix("0x") {
+                    Arg::Const(u128::from_str_radix(hex, 16).map_err(|e| e.to_string())?)
+                } else {
+                    Arg::Const(value.parse::<u128>().map_err(|e| e.to_string())?)
+                };
+                args.insert(name.to_string(), arg);
+            } else if let Some(caps) = INLINE.captures(token) {
+                let len = caps[3].parse::<usize>().map_err(|e| e.to_string())?;
+                if len == 0 || bits.len() + len > self.width {
+                    return Err(format!("invalid inline field \"{}\"!", token));
+                }
+                args.insert(
+                    caps[1].to_string(),
+                    Arg::Field(Field {
+                        segments: vec![(self.width - bits.len() - len, len)],
+                        signed: !caps[2].is_empty(),
+                        function: None,
+                    }),
+                );
+                bits.extend(std::iter::repeat_n('?', len));
+            } else if BITS.is_match(token) {
+                bits.extend(
+                    token
+                        .chars()
+                        .map(|c| if c == '0' || c == '1' { c } else { '?' }),
+                );
+            } else {
+                return Err(format!("invalid token \"{}\"!", token));
+            }
+        }
+        if !bits.is_empty() && bits.len() != self.width {
+            return Err(format!(
+                "pattern has {} bits, but insn width is {}!",
+                bits.len(),
+                self.width
+            ));
+        }
+        if let Some(mut format) = inherited {
+            for (i, bit) in bits.iter().enumerate() {
+                if *bit != '?' {
+                    if format.bits[i] != '?' && format.bits[i] != *bit {
+                        return Err(format!(
+                            "bit {} conflicts with the format!",
+                            self.width - 1 - i
+                        ));
+                    }
+                    format.bits[i] = *bit;
+                }
+            }
+            format.args.extend(args);
+            Ok(format)
+        } else {
+            if bits.is_empty() {
+                bits = vec!['?'; self.width];
+            }
+            Ok(Format { bits, args })
+        }
+    }
+
+    fn field(&self, name: &str) -> std::result::Result<Field, String> {
+        self.fields
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("undefined field \"%{}\"!", name))
+    }
+}
+
+fn u128_lit(value: u128) -> LitInt {
+    LitInt::new(&format!("{:#x}u128", value), Span::call_site())
+}
+
+fn expand_arg(arg: &Arg) -> TokenStream {
+    match arg {
+        Arg::Const(value) => {
+            let value = u128_lit(*value);
+            quote!(#value as TerminusInsnT)
+        }
+        Arg::Field(field) => {
+            let total: usize = field.segments.iter().map(|(_, len)| len).sum();
+            let segments = field.segments.iter().map(|(pos, len)| {
+                let mask = u128_lit((1u128 << len) - 1);
+                let len = *len as u32;
+                quote!(v = v.checked_shl(#len).unwrap_or(0) | (*code >> #pos) & (#mask as TerminusInsnT);)
+            });
+            let sext = if field.signed {
+                let total = total as u32;
+                quote!(
+                    if (#total as usize) < std::mem::size_of::<TerminusInsnT>() << 3 && (v >> (#total - 1)) & 1 == 1 {
+                        v |= !(((1 as TerminusInsnT) << #total) - 1);
+                    }
+                )
+            } else {
+                quote!()
+            };
+            let function = field.function.as_ref().map(|f| {
+                let f = Ident::new(f, Span::call_site());
+                quote!(v = #f(v);)
+            });
+            quote!({
+                let mut v: TerminusInsnT = 0;
+                #(#segments)*
+                #sext
+                #function
+                v
+            })
+        }
+    }
+}
+
+//a !function=f field is encoded through its inverse f_inv, which has to be in scope as f
+fn expand_encode(arg: Option<&Arg>, value: &Ident) -> TokenStream {
+    match arg {
+        Some(Arg::Field(field)) => {
+            let inverse = field.function.as_ref().map(|f| {
+                let f = Ident::new(&format!("{}_inv", f), Span::call_site());
+                quote!(let #value = #f(#value);)
+            });
+            let mut shift: usize = field.segments.iter().map(|(_, len)| len).sum();
+            let segments = field.segments.iter().map(|(pos, len)| {
+                shift -= len;
+                let mask = u128_lit((1u128 << len) - 1);
+                quote!(| ((#value >> #shift) & (#mask as TerminusInsnT)) << #pos)
+            });
+            quote!({
+                #inverse
+                0 #(#segments)*
+            })
+        }
+        _ => quote!(0),
+    }
+}
+
+fn expand_pattern(pattern: &Pattern, width: usize) -> TokenStream {
+    let name = Ident::new(
+        &crate::opcodes::struct_name(&pattern.name),
+        Span::call_site(),
+    );
+    let code = format!("{}b{}", width, pattern.bits.iter().collect::<String>());
+    let priority = if pattern.priority == 0 {
+        quote!()
+    } else {
+        let priority = LitInt::new(&pattern.priority.to_string(), Span::call_site());
+        quote!(#[priority(#priority)])
+    };
+    let operands = ["rd", "rs1", "rs2", "imm"]
+        .iter()
+        .filter_map(|op| {
+            pattern.args.get(*op).map(|arg| {
+                let method = Ident::new(op, Span::call_site());
+                let value = expand_arg(arg);
+                quote!(
+                    fn #method(&self, code: &TerminusInsnT) -> TerminusInsnT {
+                        #value
+                    }
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    //a function may widen the field, a signed one is already sign extended by imm so its sign is
+    //taken from the msb of TerminusInsnT then
+    let (imm_len, imm_unsigned) = match pattern.args.get("imm") {
+        Some(Arg::Field(field)) if field.function.is_none() => {
+            let len: usize = field.segments.iter().map(|(_, len)| len).sum();
+            (quote!(#len), !field.signed)
+        }
+        Some(Arg::Field(field)) if field.signed => {
+            (quote!(std::mem::size_of::<TerminusInsnT>() << 3), false)
+        }
+        _ => (quote!(0), true),
+    };
+    let fields = ["rd", "rs1", "rs2", "imm"]
+        .iter()
+        .filter(|op| pattern.args.contains_key(**op))
+        .collect::<Vec<_>>();
+    let idents = ["rd", "rs1", "rs2", "imm"]
+        .iter()
+        .map(|op| Ident::new(op, Span::call_site()))
+        .collect::<Vec<_>>();
+    let encodes = idents
+        .iter()
+        .map(|op| expand_encode(pattern.args.get(&op.to_string()), op))
+        .collect::<Vec<_>>();
+    quote!(
+        #[derive(Instruction)]
+        #[format(USER_DEFINE)]
+        #[code(#code)]
+        #priority
+        pub struct #name();
+        impl Format for #name {
+            #(#operands)*
+            fn imm_len(&self) -> usize {
+                #imm_len
+            }
+            fn imm_unsigned(&self) -> bool {
+                #imm_unsigned
+            }
+            fn fields(&self) -> &'static [&'static str] {
+                &[#(#fields),*]
+            }
+            #[allow(unused_variables)]
+            fn encode(&self, #(#idents: TerminusInsnT),*) -> TerminusInsnT {
+                0 #(| #encodes)*
+            }
+        }
+    )
+}
@@ -0,0 +1,195 @@
+use proc_macro2::{Span, TokenStream};
+use syn::parse::{Error, Parse, ParseStream, Result};
+use syn::punctuated::Punctuated;
+use syn::{braced, Ident, LitInt, Token};
+
+const FIELDS: [&str; 5] = ["rd", "rs1", "rs2", "imm", "op"];
+
+//"imm: 31:25 | 11:7 | sext", segments are concatenated msb first
+struct Field {
+    name: Ident,
+    segments: Vec<(usize, usize)>,
+    sext: bool,
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse::<Ident>()?;
+        if !FIELDS.contains(&name.to_string().as_str()) {
+            return Err(Error::new(
+                name.span(),
+                format!("invalid field \"{}\", valid fields are {:?}!", name, FIELDS),
+            ));
+        }
+        input.parse::<Token![:]>()?;
+        let mut segments = vec![];
+        let mut sext = false;
+        loop {
+            if input.peek(Ident) {
+                let flag = input.parse::<Ident>()?;
+                if flag != "sext" || name != "imm" {
+                    return Err(Error::new(
+                        flag.span(),
+                        format!("invalid flag \"{}\", only imm can be sext!", flag),
+                    ));
+                }
+                sext = true;
+            } else {
+                let msb = input.parse::<LitInt>()?;
+                let lsb = if input.peek(Token![:]) {
+                    input.parse::<Token![:]>()?;
+                    input.parse::<LitInt>()?
+                } else {
+                    msb.clone()
+                };
+                let (msb_value, lsb_value) = (msb.base10_parse()?, lsb.base10_parse()?);
+                if msb_value < lsb_value || msb_value >= 128 {
+                    return Err(Error::new(
+                        msb.span(),
+                        format!("invalid segment {}:{} of \"{}\"!", msb, lsb, name),
+                    ));
+                }
+                segments.push((msb_value, lsb_value));
+            }
+            if !input.peek(Token![|]) {
+                break;
+            }
+            input.parse::<Token![|]>()?;
+        }
+        if segments.is_empty() {
+            return Err(Error::new(
+                name.span(),
+                format!("\"{}\" has no bits!", name),
+            ));
+        }
+        Ok(Field {
+            name,
+            segments,
+            sext,
+        })
+    }
+}
+
+pub struct FormatDef {
+    name: Ident,
+    fields: Punctuated<Field, Token![,]>,
+}
+
+impl Parse for FormatDef {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse::<Ident>()?;
+        let content;
+        braced!(content in input);
+        Ok(FormatDef {
+            name,
+            fields: content.parse_terminated(Field::parse)?,
+        })
+    }
+}
+
+fn u128_lit(value: u128) -> LitInt {
+    LitInt::new(&format!("{:#x}u128", value), Span::call_site())
+}
+
+fn expand_field(field: &Field) -> TokenStream {
+    let method = &field.name;
+    let segments = field.segments.iter().map(|(msb, lsb)| {
+        let len = (msb - lsb + 1) as u32;
+        let mask = u128_lit((1u128 << len) - 1);
+        quote!(v = v.checked_shl(#len).unwrap_or(0) | (*code >> #lsb) & (#mask as TerminusInsnT);)
+    });
+    quote!(
+        fn #method(&self, code: &TerminusInsnT) -> TerminusInsnT {
+            let mut v: TerminusInsnT = 0;
+            #(#segments)*
+            v
+        }
+    )
+}
+
+fn expand_encode(field: Option<&Field>, value: &Ident) -> TokenStream {
+    match field {
+        Some(field) => {
+            let mut shift: usize = field.segments.iter().map(|(msb, lsb)| msb - lsb + 1).sum();
+            let segments = field.segments.iter().map(|(msb, lsb)| {
+                let len = msb - lsb + 1;
+                shift -= len;
+                let mask = u128_lit((1u128 << len) - 1);
+                quote!(| ((#value >> #shift) & (#mask as TerminusInsnT)) << #lsb)
+            });
+            quote!(0 #(#segments)*)
+        }
+        None => quote!(0),
+    }
+}
+
+pub fn expand(def: FormatDef) -> Result<TokenStream> {
+    let name = &def.name;
+    let mut bits = std::collections::HashMap::new();
+    for (i, field) in def.fields.iter().enumerate() {
+        if def.fields.iter().take(i).any(|f| f.name == field.name) {
+            return Err(Error::new(
+                field.name.span(),
+                format!("field \"{}\" is defined more than once!", field.name),
+            ));
+        }
+        //rd and rs1 may share bits as in CI, other fields may not overlap
+        for (msb, lsb) in field.segments.iter() {
+            for bit in *lsb..=*msb {
+                if let Some(other) = bits.insert(bit, field.name.to_string()) {
+                    if !(["rd", "rs1"].contains(&other.as_str())
+                        && ["rd", "rs1"].contains(&field.name.to_string().as_str()))
+                    {
+                        return Err(Error::new(
+                            field.name.span(),
+                            format!(
+                                "bit {} of \"{}\" overlaps with \"{}\"!",
+                                bit, field.name, other
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    let get = |op: &str| def.fields.iter().find(|f| f.name == op);
+    let methods = def.fields.iter().map(expand_field).collect::<Vec<_>>();
+    let imm_len: usize = get("imm").map_or(0, |f| {
+        f.segments.iter().map(|(msb, lsb)| msb - lsb + 1).sum()
+    });
+    let imm_unsigned = get("imm").is_some_and(|f| !f.sext);
+    let fields = ["rd", "rs1", "rs2", "imm"]
+        .iter()
+        .filter(|op| get(op).is_some())
+        .collect::<Vec<_>>();
+    let idents = ["rd", "rs1", "rs2", "imm"]
+        .iter()
+        .map(|op| Ident::new(op, Span::call_site()))
+        .collect::<Vec<_>>();
+    let encodes = idents
+        .iter()
+        .map(|op| expand_encode(get(&op.to_string()), op))
+        .collect::<Vec<_>>();
+    Ok(quote!(
+        pub struct #name;
+        impl DefinedFormat for #name {
+            const FORMAT: Self = #name;
+        }
+        impl Format for #name {
+            #(#methods)*
+            fn imm_len(&self) -> usize {
+                #imm_len
+            }
+            fn imm_unsigned(&self) -> bool {
+                #imm_unsigned
+            }
+            fn fields(&self) -> &'static [&'static str] {
+                &[#(#fields),*]
+            }
+            #[allow(unused_variables)]
+            fn encode(&self, #(#idents: TerminusInsnT),*) -> TerminusInsnT {
+                #(#encodes)|*
+            }
+        }
+    ))
+}
@@ -1,52 +1,132 @@
 use proc_macro2::Span;
 use regex::Regex;
 use syn::parse::Error;
-use syn::{DataStruct, DeriveInput, Ident, LitStr, NestedMeta, Result};
-
-lazy_static! {
-    static ref VALID_FORMAT_TYPE: Vec<&'static str> = vec![
-        "USER_DEFINE",
-        "R",
-        "I",
-        "S",
-        "B",
-        "U",
-        "J",
-        "CR",
-        "CIW",
-        "CI",
-        "CSS",
-        "CL",
-        "CS",
-        "CB",
-        "CA",
-        "CJ",
-    ];
-}
+use syn::{DataStruct, DeriveInput, Ident, LitInt, LitStr, NestedMeta, Result};
+
+const VALID_FORMAT_TYPE: [&str; 16] = [
+    "USER_DEFINE",
+    "R",
+    "I",
+    "S",
+    "B",
+    "U",
+    "J",
+    "CR",
+    "CIW",
+    "CI",
+    "CSS",
+    "CL",
+    "CS",
+    "CB",
+    "CA",
+    "CJ",
+];
 
 pub fn expand(ast: &DeriveInput, name: &Ident) -> Result<proc_macro2::TokenStream> {
     if let syn::Data::Struct(data) = &ast.data {
         let code_str = parse_code_attr(ast, "code")?;
-        let code = parse_code_value(&code_str);
-        let mask = parse_mask_value(&code_str);
+        let code = bits_lit(&parse_code_value(&code_str));
+        let mask = bits_lit(&parse_mask_value(&code_str));
         let format = parse_format_attr(ast)?;
+        let name_string = name.to_string();
+        let (asm, disasm) = parse_asm_attr(ast, &name_string)?;
+        let imm = parse_imm_attr(ast, &format)?;
+        let priority = parse_priority_attr(ast)?;
+        let extension = parse_extension_attr(ast)?;
+        let xlen = parse_xlen_attr(ast)?;
+        let reserved = parse_reserved_attrs(ast)?;
+        let class = parse_class_attr(ast, &format, &code_str)?;
+        let operands = parse_operands_attrs(ast, &format, &code_str)?;
+        let format_string = format.to_string();
+        let len = code_str.len().div_ceil(8);
+        let location = quote_spanned!(name.span()=> file!(), line!());
         let decoder_ident = format_ident!("{}Decoder", name);
         let registery_ident = format_ident!(
             "REGISTERY_{}",
             Ident::new(&name.to_string().to_uppercase(), name.span())
         );
-        let name_string = name.to_string();
-        check_fields(data, name)?;
+        let fields = parse_fields(data, name, &format)?;
+        let blank = if fields.is_empty() {
+            quote!(#name())
+        } else {
+            let idents = fields.iter().map(|(ident, _)| ident);
+            quote!(#name { #(#idents: 0),* })
+        };
+        let from_ir = if fields.is_empty() {
+            quote!(
+                pub fn from_ir(_: &TerminusInsnT) -> #name {
+                    #blank
+                }
+            )
+        } else {
+            //imm is sign extended only if the format or the #[imm] layout is signed
+            let values = fields.iter().map(|(ident, ty)| {
+                if ident == "imm" {
+                    quote!(#ident: InstructionImp::imm_signed(&insn, ir) as #ty)
+                } else {
+                    quote!(#ident: Format::#ident(&insn, ir) as #ty)
+                }
+            });
+            quote!(
+                pub fn from_ir(ir: &TerminusInsnT) -> #name {
+                    let insn = #blank;
+                    #name { #(#values),* }
+                }
+            )
+        };
+        //any other type is reported at its name by the bound of DefinedFormat
+        let format_check = if VALID_FORMAT_TYPE.contains(&&format_string[..]) {
+            quote!()
+        } else {
+            quote_spanned!(format.span()=>
+                const _: () = {
+                    fn defined_format<F: DefinedFormat>() {}
+                    let _ = defined_format::<#format>;
+                };
+            )
+        };
         Ok(quote!(
+            #format_check
             insn_format!(#name, #format);
             impl #name {
+                const CODE: TerminusInsnT = #code;
+                const MASK: TerminusInsnT = #mask;
+                const PRIORITY: i32 = #priority;
+                const EXTENSION: &'static str = #extension;
+                const XLEN: usize = #xlen;
                 fn new() -> Instruction {
-                    Instruction::new(#name())
+                    Instruction::new(#blank)
+                }
+                fn decoder() -> #decoder_ident {
+                    #decoder_ident(#name::new(), #name::CODE, #name::MASK, std::sync::atomic::AtomicU64::new(0))
+                }
+                #from_ir
+                pub fn encode(rd: TerminusInsnT, rs1: TerminusInsnT, rs2: TerminusInsnT, imm: TerminusInsnT) -> TerminusInsnT {
+                    let insn = #blank;
+                    Format::encode(&insn, rd, rs1, rs2, insn.imm_field(imm)) & !Self::MASK | Self::CODE
                 }
             }
-            impl InstructionImp for #name{}
+            impl InstructionImp for #name{
+                fn disasm(&self, code: &TerminusInsnT) -> String {
+                    #disasm
+                }
+                fn asm(&self) -> &'static str {
+                    #asm
+                }
+                fn name(&self) -> &'static str {
+                    #name_string
+                }
+                fn class(&self) -> InsnClass {
+                    InsnClass::#class
+                }
+                fn decode_owned(&self, code: &TerminusInsnT) -> Box<dyn InstructionImp> {
+                    Box::new(#name::from_ir(code))
+                }
+                #operands
+                #imm
+            }
 
-            struct #decoder_ident(Instruction, TerminusInsnT, TerminusInsnT);
+            struct #decoder_ident(Instruction, TerminusInsnT, TerminusInsnT, std::sync::atomic::AtomicU64);
             impl Decoder for #decoder_ident {
                 fn code(&self) ->  TerminusInsnT {
                     self.1
@@ -63,29 +143,111 @@ pub fn expand(ast: &DeriveInput, name: &Ident) -> Result<proc_macro2::TokenStrea
                 fn name(&self) -> String{
                     #name_string.to_string()
                 }
+                fn reserved(&self, ir: &TerminusInsnT) -> bool {
+                    false #(|| *ir & #reserved)*
+                }
+                fn priority(&self) -> i32 {
+                    #name::PRIORITY
+                }
+                fn extension(&self) -> &'static str {
+                    #name::EXTENSION
+                }
+                fn xlen(&self) -> usize {
+                    #name::XLEN
+                }
+                fn hits(&self) -> Option<&std::sync::atomic::AtomicU64> {
+                    Some(&self.3)
+                }
+                fn info(&self) -> InsnInfo {
+                    let (file, line) = (#location);
+                    InsnInfo {
+                        name: self.name(),
+                        format: #format_string,
+                        code: self.code(),
+                        mask: self.mask(),
+                        extension: self.extension(),
+                        xlen: self.xlen(),
+                        priority: self.priority(),
+                        class: self.decode().class(),
+                        fields: self.decode().fields(),
+                        imm: self.decode().imm_layout(),
+                        len: #len,
+                        file,
+                        line,
+                    }
+                }
             }
 
             #[distributed_slice(REGISTERY_INSN)]
-            static #registery_ident: fn(&mut GlobalInsnMap) = |map| {map.registery(#decoder_ident(#name::new(),
-                TerminusInsnT::from_str_radix(#code, 2).unwrap(),
-                TerminusInsnT::from_str_radix(#mask, 2).unwrap()
-                ))};
+            static #registery_ident: fn() -> Box<dyn Decoder> = || Box::new(#name::decoder());
         ))
     } else {
         Err(Error::new(name.span(), "Only Struct can derive"))
     }
 }
 
-fn check_fields(data: &DataStruct, name: &Ident) -> Result<()> {
-    let msg = format!("expect \'struct {}();\' !", name.to_string());
-    if let syn::Fields::Unnamed(ref field) = data.fields {
-        if !field.unnamed.is_empty() {
-            Err(Error::new(field.paren_token.span, msg))
-        } else {
-            Ok(())
+//operand fields of a format, None if the format does not tell
+fn format_fields(format: &Ident) -> Option<&'static [&'static str]> {
+    match format.to_string().as_str() {
+        "R" | "CR" | "CA" => Some(&["rd", "rs1", "rs2"]),
+        "I" | "CI" | "CL" | "CB" => Some(&["rd", "rs1", "imm"]),
+        "S" | "B" | "CS" => Some(&["rs1", "rs2", "imm"]),
+        "U" | "J" | "CIW" => Some(&["rd", "imm"]),
+        "CSS" => Some(&["rs2", "imm"]),
+        "CJ" => Some(&["imm"]),
+        _ => None,
+    }
+}
+
+//"struct Foo();" or named operand fields like "struct Foo { rd: u8, imm: i32 }", every field is
+//returned with its type
+fn parse_fields(
+    data: &DataStruct,
+    name: &Ident,
+    format: &Ident,
+) -> Result<Vec<(Ident, syn::Type)>> {
+    lazy_static! {
+        static ref INTEGER: Regex = Regex::new("^[ui](8|16|32|64|128|size)$").unwrap();
+    }
+    let msg = format!(
+        "expect \'struct {}();\' or operand fields like \'struct {} {{ rd: u8, imm: i32 }}\' !",
+        name, name
+    );
+    match data.fields {
+        syn::Fields::Unnamed(ref field) if field.unnamed.is_empty() => Ok(vec![]),
+        syn::Fields::Unnamed(ref field) => Err(Error::new(field.paren_token.span, msg)),
+        syn::Fields::Named(ref field) => {
+            let valid = format_fields(format).unwrap_or(&["rd", "rs1", "rs2", "imm"]);
+            field
+                .named
+                .iter()
+                .map(|f| {
+                    let ident = f.ident.clone().unwrap();
+                    if !valid.contains(&ident.to_string().as_str()) {
+                        return Err(Error::new(
+                            ident.span(),
+                            format!(
+                                "\"{}\" is not an operand of format {}, valid fields are {}!",
+                                ident,
+                                format,
+                                valid.join(", ")
+                            ),
+                        ));
+                    }
+                    let ty = &f.ty;
+                    let ty_string = quote!(#ty).to_string();
+                    if INTEGER.is_match(&ty_string) {
+                        Ok((ident, f.ty.clone()))
+                    } else {
+                        Err(Error::new(
+                            ident.span(),
+                            format!("type of \"{}\" should be a primitive integer!", ident),
+                        ))
+                    }
+                })
+                .collect()
         }
-    } else {
-        Err(Error::new(name.span(), msg))
+        syn::Fields::Unit => Err(Error::new(name.span(), msg)),
     }
 }
 
@@ -108,7 +270,8 @@ fn parse_raw_bits(lit: &LitStr) -> Result<String> {
         static ref BITS_REP: Regex = Regex::new("_").unwrap();
     }
     if let Some(caps) = VALID_CODE.captures(&code) {
-        let len = usize::from_str_radix(&caps[1], 10)
+        let len = caps[1]
+            .parse::<usize>()
             .map_err(|e| Error::new(lit.span(), e.to_string()))?;
         if len == 0 {
             return Err(Error::new(lit.span(), "length of code can not be zero!"));
@@ -157,20 +320,31 @@ fn parse_mask_value(bits: &str) -> String {
     parse_code_value(&ZERO.replace_all(bits, "1"))
 }
 
+fn bits_lit(bits: &str) -> LitInt {
+    LitInt::new(&format!("0b{}", bits), Span::call_site())
+}
+
 fn parse_format_attr(ast: &DeriveInput) -> Result<Ident> {
     let Attr { ident, attr } = parse_attr(ast, "format")?;
     if let NestedMeta::Meta(syn::Meta::Path(ref path)) = attr {
+        //formats other than the builtin ones and USER_DEFINE are defined by define_format!, names
+        //differing from a builtin one only in case are taken for misspelt builtin ones
         if let Some(ident) = path.get_ident() {
-            if VALID_FORMAT_TYPE.contains(&&format!("{}", ident)[..]) {
-                Ok(ident.clone())
-            } else {
+            let name = ident.to_string();
+            if !VALID_FORMAT_TYPE.contains(&&name[..])
+                && VALID_FORMAT_TYPE
+                    .iter()
+                    .any(|f| f.eq_ignore_ascii_case(&name))
+            {
                 Err(Error::new(
                     ident.span(),
                     format!(
-                        "invalid \"{}\" value \"{}\", valid values are {:?}",
-                        "format", ident, *VALID_FORMAT_TYPE
+                        "invalid \"{}\" value \"{}\", valid values are {:?} or a define_format! type",
+                        "format", ident, VALID_FORMAT_TYPE
                     ),
                 ))
+            } else {
+                Ok(ident.clone())
             }
         } else {
             Err(Error::new(
@@ -186,6 +360,617 @@ fn parse_format_attr(ast: &DeriveInput) -> Result<Ident> {
     }
 }
 
+fn parse_asm_attr(ast: &DeriveInput, name: &str) -> Result<(String, proc_macro2::TokenStream)> {
+    if let Some(Attr { ident, attr }) = parse_optional_attr(ast, "asm")? {
+        if let NestedMeta::Lit(syn::Lit::Str(ref raw)) = attr {
+            Ok((raw.value(), expand_disasm(&parse_asm_template(raw)?)?))
+        } else {
+            Err(Error::new(
+                ident.span(),
+                format!("\"{}\" is expected as string!", "asm"),
+            ))
+        }
+    } else {
+        Ok((
+            name.to_string(),
+            expand_disasm(&[AsmPiece::Lit(name.to_string())])?,
+        ))
+    }
+}
+
+enum AsmPiece {
+    Lit(String),
+    Operand(String, Option<String>),
+}
+
+fn parse_asm_template(lit: &LitStr) -> Result<Vec<AsmPiece>> {
+    lazy_static! {
+        static ref OPERAND: Regex = Regex::new("^([a-z0-9]+)(?::([a-z]+))?$").unwrap();
+    }
+    let template = lit.value();
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| Error::new(lit.span(), "unclosed \"{\" in asm template!"))?;
+                let caps = OPERAND.captures(&rest[..end]).ok_or_else(|| {
+                    Error::new(
+                        lit.span(),
+                        format!("invalid operand \"{{{}}}\" in asm template!", &rest[..end]),
+                    )
+                })?;
+                let field = caps[1].to_string();
+                let spec = caps.get(2).map(|m| m.as_str().to_string());
+                check_asm_operand(lit, &field, &spec)?;
+                if !text.is_empty() {
+                    pieces.push(AsmPiece::Lit(std::mem::take(&mut text)));
+                }
+                pieces.push(AsmPiece::Operand(field, spec));
+                chars = rest[end + 1..].chars();
+            }
+            '}' => return Err(Error::new(lit.span(), "unmatched \"}\" in asm template!")),
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(AsmPiece::Lit(text));
+    }
+    Ok(pieces)
+}
+
+fn check_asm_operand(lit: &LitStr, field: &str, spec: &Option<String>) -> Result<()> {
+    let valid_specs: &[&str] = match field {
+        "rd" | "rs1" | "rs2" => &["x", "f", "v"],
+        "imm" => &["d", "u", "x"],
+        _ => {
+            return Err(Error::new(
+                lit.span(),
+                format!(
+                    "invalid operand \"{}\" in asm template, valid operands are {:?}",
+                    field,
+                    ["rd", "rs1", "rs2", "imm"]
+                ),
+            ))
+        }
+    };
+    match spec {
+        Some(spec) if !valid_specs.contains(&spec.as_str()) => Err(Error::new(
+            lit.span(),
+            format!(
+                "invalid spec \"{}\" of operand \"{}\", valid specs are {:?}",
+                spec, field, valid_specs
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn expand_disasm(pieces: &[AsmPiece]) -> Result<proc_macro2::TokenStream> {
+    let mut fmt = String::new();
+    let mut args = vec![];
+    for piece in pieces {
+        match piece {
+            AsmPiece::Lit(text) => fmt.push_str(&text.replace('{', "{{").replace('}', "}}")),
+            AsmPiece::Operand(field, spec) => {
+                let accessor = format_ident!("{}", field);
+                match (field.as_str(), spec.as_deref()) {
+                    ("imm", Some("x")) => {
+                        fmt.push_str("{:#x}");
+                        args.push(quote!(self.imm_value(code) >> self.imm_shift()));
+                    }
+                    ("imm", Some("u")) => {
+                        fmt.push_str("{}");
+                        args.push(quote!(self.imm_value(code) >> self.imm_shift()));
+                    }
+                    ("imm", _) => {
+                        fmt.push_str("{}");
+                        args.push(quote!(self.imm_signed(code) >> self.imm_shift()));
+                    }
+                    (_, prefix) => {
+                        fmt.push_str(prefix.unwrap_or("x"));
+                        fmt.push_str("{}");
+                        args.push(quote!(self.#accessor(code)));
+                    }
+                }
+            }
+        }
+    }
+    Ok(quote!(format!(#fmt #(, #args)*)))
+}
+
+fn format_imm_len(format: &str) -> Option<usize> {
+    match format {
+        "I" | "S" => Some(12),
+        "B" => Some(13),
+        "U" => Some(32),
+        "J" => Some(21),
+        "CIW" | "CB" => Some(8),
+        "CI" | "CSS" => Some(6),
+        "CL" | "CS" => Some(5),
+        "CJ" => Some(11),
+        "R" | "CR" | "CA" => Some(0),
+        _ => None,
+    }
+}
+
+fn parse_imm_attr(ast: &DeriveInput, format: &Ident) -> Result<proc_macro2::TokenStream> {
+    if let Some(Attr { ident, attr }) = parse_optional_attr(ast, "imm")? {
+        if let NestedMeta::Lit(syn::Lit::Str(ref raw)) = attr {
+            let (segments, sext) = parse_imm_layout(raw, format)?;
+            expand_imm_layout(&raw.value(), &segments, sext)
+        } else {
+            Err(Error::new(
+                ident.span(),
+                format!("\"{}\" is expected as string!", "imm"),
+            ))
+        }
+    } else {
+        Ok(quote!())
+    }
+}
+
+fn parse_priority_attr(ast: &DeriveInput) -> Result<i32> {
+    if let Some(Attr { ident, attr }) = parse_optional_attr(ast, "priority")? {
+        if let NestedMeta::Lit(syn::Lit::Int(ref raw)) = attr {
+            raw.base10_parse::<i32>()
+        } else {
+            Err(Error::new(
+                ident.span(),
+                format!("\"{}\" is expected as integer!", "priority"),
+            ))
+        }
+    } else {
+        Ok(0)
+    }
+}
+
+//#[reads("..")] and #[writes("..")] list "[x|f|v:]rd|rs1|rs2", fixed registers like "x1" and
+//"csr" for the csr number in imm, without them the compressed formats whose fields alias or imply
+//registers take the operands of their opcode, the other formats write rd and read rs1/rs2 if they
+//have the field, in the register file given by #[asm]
+fn parse_operands_attrs(
+    ast: &DeriveInput,
+    format: &Ident,
+    code: &str,
+) -> Result<proc_macro2::TokenStream> {
+    lazy_static! {
+        static ref FIELD: Regex = Regex::new("^(?:([xfv]):)?(rd|rs1|rs2)$").unwrap();
+        static ref FIXED: Regex = Regex::new("^([xfv])([0-9]+)$").unwrap();
+    }
+    let mut files = std::collections::HashMap::new();
+    if let Some(Attr {
+        attr: NestedMeta::Lit(syn::Lit::Str(ref raw)),
+        ..
+    }) = parse_optional_attr(ast, "asm")?
+    {
+        for piece in parse_asm_template(raw)? {
+            if let AsmPiece::Operand(field, Some(spec)) = piece {
+                files.insert(field, spec);
+            }
+        }
+    }
+    let reg = |file: &str, index: proc_macro2::TokenStream| match file {
+        "f" => quote!(Reg::F(#index as u8)),
+        "v" => quote!(Reg::V(#index as u8)),
+        _ => quote!(Reg::X(#index as u8)),
+    };
+    let implied = compressed_operands(&format.to_string(), code);
+    let mut sets = vec![];
+    for (i, (name, defaults)) in [("reads", &["rs1", "rs2"][..]), ("writes", &["rd"][..])]
+        .iter()
+        .enumerate()
+    {
+        let set = Ident::new(name, Span::call_site());
+        let mut pushes = vec![];
+        let items = if let Some(Attr { ident, attr }) = parse_optional_attr(ast, name)? {
+            let raw = if let NestedMeta::Lit(syn::Lit::Str(ref raw)) = attr {
+                raw.clone()
+            } else {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("\"{}\" is expected as string!", name),
+                ));
+            };
+            let items = raw
+                .value()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>();
+            Some((items, raw.span()))
+        } else if let Some(implied) = implied {
+            //fields of the opcode operands are in the register file given by #[asm]
+            let items = [implied.0, implied.1][i]
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| match files.get(s) {
+                    Some(file) if FIELD.is_match(s) => format!("{}:{}", file, s),
+                    _ => s.to_string(),
+                })
+                .collect::<Vec<_>>();
+            Some((items, format.span()))
+        } else if format == "CI" {
+            return Err(Error::new(
+                format.span(),
+                format!(
+                    "operands of CI inst {} can not be inferred from its opcode, \"{}\" is expected!",
+                    ast.ident, name
+                ),
+            ));
+        } else {
+            None
+        };
+        if let Some((items, span)) = items {
+            for item in items.iter() {
+                let value = if item == "csr" {
+                    quote!(Reg::Csr(self.imm(code) as u16))
+                } else if let Some(caps) = FIELD.captures(item) {
+                    let field = Ident::new(&caps[2], Span::call_site());
+                    reg(
+                        caps.get(1).map_or("x", |m| m.as_str()),
+                        quote!(self.#field(code)),
+                    )
+                } else if let Some(index) = FIXED
+                    .captures(item)
+                    .and_then(|caps| caps[2].parse::<u8>().ok().filter(|i| *i < 32))
+                {
+                    reg(&item[..1], quote!(#index))
+                } else {
+                    return Err(Error::new(
+                        span,
+                        format!(
+                            "invalid operand \"{}\", valid format is csr, [x|f|v][0-9]+ or [x|f|v:](rd|rs1|rs2)!",
+                            item
+                        ),
+                    ));
+                };
+                pushes.push(quote!(operands.#set.push(#value);));
+            }
+        } else {
+            for field in defaults.iter() {
+                let method = Ident::new(field, Span::call_site());
+                let value = reg(
+                    files.get(*field).map_or("x", |s| s.as_str()),
+                    quote!(self.#method(code)),
+                );
+                pushes.push(quote!(
+                    if self.fields().contains(&#field) {
+                        operands.#set.push(#value);
+                    }
+                ));
+            }
+        }
+        sets.push(pushes);
+    }
+    let (reads, writes) = (&sets[0], &sets[1]);
+    Ok(quote!(
+        fn operands(&self, code: &TerminusInsnT) -> Operands {
+            let mut operands = Operands::default();
+            #(#reads)*
+            #(#writes)*
+            operands
+        }
+    ))
+}
+
+//value of bits lsb..lsb+width of the code, None unless all of them are fixed
+fn code_field(code: &str, lsb: usize, width: usize) -> Option<u32> {
+    let end = code.len().checked_sub(lsb)?;
+    let bits = &code[end.checked_sub(width)?..end];
+    u32::from_str_radix(bits, 2).ok()
+}
+
+//(reads, writes) of the compressed formats whose fields do not tell them: CI and CR alias rs1 with
+//rd, CB has rd without writing it in branches and CSS and CIW address the stack through x2, the
+//opcode is told by the quadrant, funct3, bit 12 and the fixed rd/rs2 fields, None if it is not known
+fn compressed_operands(format: &str, code: &str) -> Option<(&'static str, &'static str)> {
+    let op = (code_field(code, 0, 2)?, code_field(code, 13, 3));
+    let rd = code_field(code, 7, 5);
+    let rs2 = code_field(code, 2, 5);
+    match (format, op) {
+        ("CSS", _) => Some(("x2, rs2", "")),
+        ("CIW", _) => Some(("x2", "rd")),
+        //c.addi, c.addiw, c.slli
+        ("CI", (1, Some(0))) | ("CI", (1, Some(1))) | ("CI", (2, Some(0))) => Some(("rs1", "rd")),
+        //c.li
+        ("CI", (1, Some(2))) => Some(("", "rd")),
+        //c.addi16sp, c.lui
+        ("CI", (1, Some(3))) if rd == Some(2) => Some(("rs1", "rd")),
+        ("CI", (1, Some(3))) => Some(("", "rd")),
+        //c.fldsp, c.lwsp, c.flwsp/c.ldsp
+        ("CI", (2, Some(1..=3))) => Some(("x2", "rd")),
+        //c.beqz, c.bnez
+        ("CB", (1, Some(6..=7))) => Some(("rs1", "")),
+        //c.jal links to x1, c.j
+        ("CJ", (1, Some(1))) => Some(("", "x1")),
+        ("CJ", (1, Some(5))) => Some(("", "")),
+        ("CR", (2, Some(4))) => match (code_field(code, 12, 1)?, rd, rs2) {
+            //c.ebreak, c.jalr, c.jr
+            (1, Some(0), Some(0)) => Some(("", "")),
+            (1, _, Some(0)) => Some(("rs1", "x1")),
+            (0, _, Some(0)) => Some(("rs1", "")),
+            //c.mv
+            (0, _, _) => Some(("rs2", "rd")),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+//#[class(..)] takes the lowercase variant of InsnClass, without it the class follows the opcode of
+//control transfers and fences, then the format, B formats are branches, J and CJ ones jumps
+fn parse_class_attr(ast: &DeriveInput, format: &Ident, code: &str) -> Result<Ident> {
+    const CLASSES: [&str; 11] = [
+        "other", "branch", "jump", "call", "return", "load", "store", "amo", "csr", "fence",
+        "system",
+    ];
+    let class = if let Some(attr) = ast
+        .attrs
+        .iter()
+        .find(|a| a.path.segments.len() == 1 && a.path.segments[0].ident == "class")
+    {
+        let class = attr.parse_args_with(<Ident as syn::ext::IdentExt>::parse_any)?;
+        if !CLASSES.contains(&&class.to_string()[..]) {
+            return Err(Error::new(
+                class.span(),
+                format!(
+                    "invalid \"{}\" value \"{}\", valid values are {:?}",
+                    "class", class, CLASSES
+                ),
+            ));
+        }
+        class.to_string()
+    } else if let Some(class) = implied_class(&format.to_string(), code) {
+        class.to_string()
+    } else {
+        //a block cache must know whether the instruction leaves straight-line execution
+        return Err(Error::new(
+            format.span(),
+            format!(
+                "class of inst {} can not be inferred from its opcode, \"{}\" is expected!",
+                ast.ident, "class"
+            ),
+        ));
+    };
+    let mut variant = class;
+    variant[..1].make_ascii_uppercase();
+    Ok(Ident::new(&variant, Span::call_site()))
+}
+
+//the class told by the opcode and format, None if the code covers instructions of different
+//classes among which one transfers control
+fn implied_class(format: &str, code: &str) -> Option<&'static str> {
+    let rd = code_field(code, 7, 5);
+    let rs2 = code_field(code, 2, 5);
+    let class = match (code.len(), code_field(code, 0, 2)) {
+        (32, Some(3)) => match code_field(code, 0, 7) {
+            Some(0b1100011) => Some("branch"),
+            //jal and jalr linking to x1 are calls
+            Some(0b1101111) | Some(0b1100111) if rd == Some(1) => Some("call"),
+            Some(0b1101111) | Some(0b1100111) => Some("jump"),
+            Some(0b0001111) => Some("fence"),
+            //ecall, ebreak, xret and wfi, the others access csrs
+            Some(0b1110011) => match code_field(code, 12, 3)? {
+                0 => Some("system"),
+                _ => Some("csr"),
+            },
+            _ => None,
+        },
+        //c.beqz, c.bnez
+        (16, Some(1)) if format == "CB" && code_field(code, 13, 3)? >= 6 => Some("branch"),
+        //c.jal links to x1, c.j
+        (16, Some(1)) if format == "CJ" && code_field(code, 13, 3)? == 1 => Some("call"),
+        (16, Some(2)) if format == "CR" && code_field(code, 13, 3)? == 4 => {
+            //c.ebreak, c.jalr, c.jr, a nonzero rs2 gives c.mv and c.add, whose masks leave the
+            //ones with rs2 0 to the others
+            match (code_field(code, 12, 1), rd, rs2) {
+                (Some(1), Some(0), Some(0)) => Some("system"),
+                (Some(1), _, Some(0)) => Some("call"),
+                (Some(0), _, Some(0)) => Some("jump"),
+                (None, _, Some(0)) => return None,
+                _ => Some("other"),
+            }
+        }
+        _ => None,
+    };
+    class.or(match format {
+        "B" => Some("branch"),
+        "J" | "CJ" => Some("jump"),
+        _ => Some("other"),
+    })
+}
+
+//every #[reserved("..")] pattern becomes "mask == code" to test the instruction word with
+fn parse_reserved_attrs(ast: &DeriveInput) -> Result<Vec<proc_macro2::TokenStream>> {
+    let mut reserved = vec![];
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|a| a.path.segments.len() == 1 && a.path.segments[0].ident == "reserved")
+    {
+        let ident = &attr.path.segments[0].ident;
+        let meta = attr.parse_meta()?;
+        let raw = match meta {
+            syn::Meta::List(ref list) if list.nested.len() == 1 => match list.nested[0] {
+                NestedMeta::Lit(syn::Lit::Str(ref raw)) => raw.clone(),
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!(
+                            "\"{}\" is expected as string with \"0b\" prefix!",
+                            "reserved"
+                        ),
+                    ))
+                }
+            },
+            _ => {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("\"{}\" is expected to be a single value", "reserved"),
+                ))
+            }
+        };
+        let bits = parse_raw_bits(&raw)?;
+        let code = bits_lit(&parse_code_value(&bits));
+        let mask = bits_lit(&parse_mask_value(&bits));
+        reserved.push(quote!(#mask == #code));
+    }
+    Ok(reserved)
+}
+
+fn parse_extension_attr(ast: &DeriveInput) -> Result<String> {
+    if let Some(Attr { ident, attr }) = parse_optional_attr(ast, "extension")? {
+        if let NestedMeta::Lit(syn::Lit::Str(ref raw)) = attr {
+            Ok(raw.value())
+        } else {
+            Err(Error::new(
+                ident.span(),
+                format!("\"{}\" is expected as string!", "extension"),
+            ))
+        }
+    } else {
+        Ok(String::new())
+    }
+}
+
+fn parse_xlen_attr(ast: &DeriveInput) -> Result<usize> {
+    if let Some(Attr { ident, attr }) = parse_optional_attr(ast, "xlen")? {
+        if let NestedMeta::Lit(syn::Lit::Int(ref raw)) = attr {
+            let xlen = raw.base10_parse::<usize>()?;
+            if [32, 64, 128].contains(&xlen) {
+                Ok(xlen)
+            } else {
+                Err(Error::new(
+                    raw.span(),
+                    format!("invalid xlen {}, valid xlen is 32, 64 or 128!", xlen),
+                ))
+            }
+        } else {
+            Err(Error::new(
+                ident.span(),
+                format!("\"{}\" is expected as integer!", "xlen"),
+            ))
+        }
+    } else {
+        Ok(0)
+    }
+}
+
+//"msb[:lsb]|..." optionally followed by "|sext" for a signed immediate, which is unsigned otherwise
+fn parse_imm_layout(lit: &LitStr, format: &Ident) -> Result<(Vec<(usize, usize)>, bool)> {
+    lazy_static! {
+        static ref SEGMENT: Regex = Regex::new("^([0-9]+)(?::([0-9]+))?$").unwrap();
+    }
+    let layout = lit.value();
+    let mut segments = vec![];
+    let mut sext = false;
+    let mut bits = 0u128;
+    for segment in layout.split('|') {
+        if segment.trim() == "sext" && !sext {
+            sext = true;
+            continue;
+        }
+        let caps = SEGMENT.captures(segment.trim()).ok_or_else(|| {
+            Error::new(
+                lit.span(),
+                format!(
+                    "invalid imm segment \"{}\", valid format is ^[0-9]+(:[0-9]+)?(|[0-9]+(:[0-9]+)?)*(|sext)?!",
+                    segment
+                ),
+            )
+        })?;
+        let msb = caps[1]
+            .parse::<usize>()
+            .map_err(|e| Error::new(lit.span(), e.to_string()))?;
+        let lsb = caps.get(2).map_or(Ok(msb), |m| {
+            m.as_str()
+                .parse::<usize>()
+                .map_err(|e| Error::new(lit.span(), e.to_string()))
+        })?;
+        if msb < lsb || msb >= 128 {
+            return Err(Error::new(
+                lit.span(),
+                format!("invalid imm segment \"{}\"!", segment),
+            ));
+        }
+        for bit in lsb..=msb {
+            if bits & (1 << bit) != 0 {
+                return Err(Error::new(
+                    lit.span(),
+                    format!("imm bit {} is defined more than once!", bit),
+                ));
+            }
+            bits |= 1 << bit;
+        }
+        segments.push((msb, lsb));
+    }
+    let len: usize = segments.iter().map(|(msb, lsb)| msb - lsb + 1).sum();
+    if let Some(imm_len) = format_imm_len(&format.to_string()) {
+        if len != imm_len {
+            return Err(Error::new(
+                lit.span(),
+                format!(
+                    "imm layout defines {} bits but format \"{}\" has {} imm bits!",
+                    len, format, imm_len
+                ),
+            ));
+        }
+    }
+    Ok((segments, sext))
+}
+
+//imm_width is the width imm_sext extends from, 0 keeps the immediate unsigned
+fn expand_imm_layout(
+    layout: &str,
+    segments: &[(usize, usize)],
+    sext: bool,
+) -> Result<proc_macro2::TokenStream> {
+    let mut pos: usize = segments.iter().map(|(msb, lsb)| msb - lsb + 1).sum();
+    let width = if sext {
+        segments.iter().map(|(msb, _)| msb + 1).max().unwrap_or(0)
+    } else {
+        0
+    };
+    let mut values = vec![];
+    let mut fields = vec![];
+    for (msb, lsb) in segments {
+        let len = msb - lsb + 1;
+        pos -= len;
+        let mask = LitInt::new(&format!("{:#x}", (1u128 << len) - 1), Span::call_site());
+        values.push(quote!(((raw >> #pos) & #mask) << #lsb));
+        fields.push(quote!(((value >> #lsb) & #mask) << #pos));
+    }
+    Ok(quote!(
+        fn imm_value(&self, code: &TerminusInsnT) -> TerminusInsnT {
+            let raw = self.imm(code);
+            0 #(| #values)*
+        }
+        fn imm_width(&self) -> usize {
+            #width
+        }
+        fn imm_field(&self, value: TerminusInsnT) -> TerminusInsnT {
+            0 #(| #fields)*
+        }
+        fn imm_layout(&self) -> &'static str {
+            #layout
+        }
+    ))
+}
+
 struct Attr {
     ident: Ident,
     attr: NestedMeta,
@@ -198,6 +983,11 @@ impl Attr {
 }
 
 fn parse_attr(ast: &DeriveInput, name: &str) -> Result<Attr> {
+    parse_optional_attr(ast, name)?
+        .ok_or_else(|| Error::new(Span::call_site(), format!("attr \"{}\" missed", name)))
+}
+
+fn parse_optional_attr(ast: &DeriveInput, name: &str) -> Result<Option<Attr>> {
     if let Some(attr) = ast
         .attrs
         .iter()
@@ -206,10 +996,10 @@ fn parse_attr(ast: &DeriveInput, name: &str) -> Result<Attr> {
         let meta = attr.parse_meta()?;
         if let syn::Meta::List(ref nested_meta) = meta {
             if nested_meta.nested.len() == 1 {
-                Ok(Attr::new(
+                Ok(Some(Attr::new(
                     attr.path.segments[0].ident.clone(),
                     nested_meta.nested[0].clone(),
-                ))
+                )))
             } else {
                 Err(Error::new(
                     attr.path.segments[0].ident.span(),
@@ -223,9 +1013,6 @@ fn parse_attr(ast: &DeriveInput, name: &str) -> Result<Attr> {
             ))
         }
     } else {
-        Err(Error::new(
-            Span::call_site(),
-            format!("attr \"{}\" missed", name),
-        ))
+        Ok(None)
     }
 }
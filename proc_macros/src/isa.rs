@@ -0,0 +1,216 @@
+use proc_macro2::TokenStream;
+use syn::parse::{Parse, ParseStream, Result};
+use syn::punctuated::Punctuated;
+use syn::{Ident, LitStr, Token};
+
+//"isa! { A, B }", the encodings are only known once the derived consts are evaluated, so the
+//dispatch table and the overlap checks are built by const fns in the expansion
+pub struct Isa(Punctuated<Ident, Token![,]>);
+
+impl Parse for Isa {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Isa(input.parse_terminated(Ident::parse)?))
+    }
+}
+
+pub fn expand(isa: Isa) -> TokenStream {
+    let names = isa.0.iter().collect::<Vec<_>>();
+    let name_strings = names
+        .iter()
+        .map(|name| LitStr::new(&name.to_string(), name.span()));
+    let count = names.len();
+    let indices = 0..count;
+    //pairs matching a common word that neither #[priority] nor mask specificity can order are
+    //reported at the later one
+    let checks = names.iter().enumerate().map(|(i, name)| {
+        let msg = format!("inst {} overlaps with an inst listed before it!", name);
+        quote_spanned!(name.span()=>
+            const _: () = assert!(!StaticInsnMap::overlapped(#i), #msg);
+        )
+    });
+    quote! {
+        pub type GlobalInsnMap = StaticInsnMap;
+        /// Every instruction listed in `isa!`, the flags tell which ones are registered.
+        pub struct StaticInsnMap(Vec<Box<dyn Decoder>>, Vec<bool>, DecodeContext);
+
+        #(#checks)*
+
+        impl StaticInsnMap {
+            //code, mask, priority, xlen and name of the listed instructions
+            const LISTED: [(u128, u128, i32, usize, &'static str); #count] = [
+                #((#names::CODE as u128, #names::MASK as u128, #names::PRIORITY, #names::XLEN, #name_strings),)*
+            ];
+            //the listed instructions a word can decode to by its low 7 bits (the length bits and
+            //the 32-bit opcode) in precedence order, those of key are
+            //DISPATCH[OFFSETS[key]..OFFSETS[key + 1]]
+            const OFFSETS: [usize; 129] = StaticInsnMap::offsets();
+            const DISPATCH: [usize; StaticInsnMap::OFFSETS[128]] = StaticInsnMap::dispatch();
+
+            pub fn new() -> StaticInsnMap {
+                StaticInsnMap((0..#count).map(StaticInsnMap::listed).collect(), vec![false; #count], DecodeContext::default())
+            }
+
+            fn listed(i: usize) -> Box<dyn Decoder> {
+                match i {
+                    #(#indices => Box::new(#names::decoder()),)*
+                    _ => unreachable!(),
+                }
+            }
+
+            fn enable(&mut self, i: usize) {
+                if !self.1[i] {
+                    let ties = self.iter().filter_map(|v| Conflict::xlen_tie(self.0[i].as_ref(), v)).collect::<Vec<_>>();
+                    self.2.ties.extend(ties);
+                    self.1[i] = true
+                }
+            }
+
+            const fn candidate(i: usize, key: usize) -> bool {
+                let (code, mask, ..) = StaticInsnMap::LISTED[i];
+                (code ^ key as u128) & mask & 0x7f == 0
+            }
+
+            //same order as Decoder::precedence
+            const fn precedes(a: usize, b: usize) -> bool {
+                let (_, a_mask, a_priority, _, a_name) = StaticInsnMap::LISTED[a];
+                let (_, b_mask, b_priority, _, b_name) = StaticInsnMap::LISTED[b];
+                if a_priority != b_priority {
+                    return a_priority > b_priority;
+                }
+                if a_mask.count_ones() != b_mask.count_ones() {
+                    return a_mask.count_ones() > b_mask.count_ones();
+                }
+                let (a_name, b_name) = (a_name.as_bytes(), b_name.as_bytes());
+                let mut k = 0;
+                while k < a_name.len() && k < b_name.len() {
+                    if a_name[k] != b_name[k] {
+                        return a_name[k] < b_name[k];
+                    }
+                    k += 1;
+                }
+                a_name.len() < b_name.len()
+            }
+
+            const fn overlapped(i: usize) -> bool {
+                let (code, mask, priority, xlen, _) = StaticInsnMap::LISTED[i];
+                let mut j = 0;
+                while j < i {
+                    let (other_code, other_mask, other_priority, other_xlen, _) = StaticInsnMap::LISTED[j];
+                    if (code ^ other_code) & mask & other_mask == 0
+                        && priority == other_priority
+                        && mask.count_ones() == other_mask.count_ones()
+                        && (xlen == 0 || other_xlen == 0 || xlen == other_xlen)
+                    {
+                        return true;
+                    }
+                    j += 1;
+                }
+                false
+            }
+
+            const fn offsets() -> [usize; 129] {
+                let mut offsets = [0; 129];
+                let mut key = 0;
+                while key < 128 {
+                    let mut i = 0;
+                    offsets[key + 1] = offsets[key];
+                    while i < StaticInsnMap::LISTED.len() {
+                        if StaticInsnMap::candidate(i, key) {
+                            offsets[key + 1] += 1;
+                        }
+                        i += 1;
+                    }
+                    key += 1;
+                }
+                offsets
+            }
+
+            //candidates of every key insertion sorted by precedence
+            const fn dispatch() -> [usize; StaticInsnMap::OFFSETS[128]] {
+                let mut dispatch = [0; StaticInsnMap::OFFSETS[128]];
+                let mut key = 0;
+                while key < 128 {
+                    let start = StaticInsnMap::OFFSETS[key];
+                    let mut end = start;
+                    let mut i = 0;
+                    while i < StaticInsnMap::LISTED.len() {
+                        if StaticInsnMap::candidate(i, key) {
+                            let mut j = end;
+                            while j > start && StaticInsnMap::precedes(i, dispatch[j - 1]) {
+                                dispatch[j] = dispatch[j - 1];
+                                j -= 1;
+                            }
+                            dispatch[j] = i;
+                            end += 1;
+                        }
+                        i += 1;
+                    }
+                    key += 1;
+                }
+                dispatch
+            }
+
+            //the registered instruction ir decodes to
+            fn lookup(&self, ir: TerminusInsnT) -> Option<usize> {
+                let key = (ir & 0x7f) as usize;
+                StaticInsnMap::DISPATCH[StaticInsnMap::OFFSETS[key]..StaticInsnMap::OFFSETS[key + 1]]
+                    .iter()
+                    .cloned()
+                    .find(|&i| {
+                        let (code, mask, ..) = StaticInsnMap::LISTED[i];
+                        self.1[i] && ir as u128 & mask == code
+                    })
+            }
+        }
+
+        impl InsnMap for StaticInsnMap {
+            fn registery<T: 'static + Decoder>(&mut self, decoder: T) {
+                match self.0.iter().position(|d| {
+                    (d.code(), d.mask(), d.priority(), d.xlen())
+                        == (decoder.code(), decoder.mask(), decoder.priority(), decoder.xlen())
+                }) {
+                    Some(i) => self.enable(i),
+                    None => panic!("inst {} is not listed in isa!", decoder.name()),
+                }
+            }
+
+            fn registery_linked<F: Fn(&dyn Decoder) -> bool>(&mut self, filter: F) {
+                for i in 0..self.0.len() {
+                    if filter(self.0[i].as_ref()) {
+                        self.enable(i)
+                    }
+                }
+            }
+
+            fn disable<T: 'static + Decoder>(&mut self, decoder: T) {
+                self.2.disable(decoder)
+            }
+
+            fn disable_linked<F: Fn(&dyn Decoder) -> bool>(&mut self, filter: F) {
+                for i in 0..self.0.len() {
+                    if !self.1[i] && filter(self.0[i].as_ref()) {
+                        self.2.disable(StaticInsnMap::listed(i))
+                    }
+                }
+            }
+
+            fn lock(&mut self) {
+                self.2.lock()
+            }
+
+            fn decode(&self, ir: &TerminusInsnT) -> Result<&Instruction, Error> {
+                self.2.result(self.lookup(*ir).map(|i| self.0[i].as_ref()), ir)
+            }
+
+            fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a dyn Decoder> + 'a> {
+                Box::new(
+                    self.0
+                        .iter()
+                        .zip(self.1.iter())
+                        .filter(|(_, enabled)| **enabled)
+                        .map(|(d, _)| d.as_ref()),
+                )
+            }
+        }
+    }
+}
@@ -7,7 +7,11 @@ extern crate syn;
 extern crate terminus_macros;
 extern crate regex;
 
+mod decodetree;
+mod format;
 mod insn;
+mod isa;
+mod opcodes;
 
 use proc_macro::TokenStream;
 use syn::DeriveInput;
@@ -55,7 +59,74 @@ use syn::DeriveInput;
 /// # }
 /// ```
 ///
-#[proc_macro_derive(Instruction, attributes(code, format))]
+/// The attributes of the derive:
+/// - `#[code("..")]` gives the width in bits followed by the encoding msb first, `?` marks
+///   operand bits and `_` is ignored.
+/// - `#[format(..)]` names a built-in format, a `define_format!` type or `USER_DEFINE`. A
+///   built-in name in the wrong case and a type not defined by `define_format!` are errors at the
+///   name.
+/// - `#[asm("..")]` is the assembly template used by `disasm` and `assemble`. `{rd}`, `{rs1}`
+///   and `{rs2}` print registers with an optional register file (`{rd:f}`), `{imm}` prints
+///   `imm_signed >> imm_shift`, negative only if the immediate is signed, and `{imm:u}` and
+///   `{imm:x}` print `imm_value >> imm_shift` in decimal and hex. Without it only the name is
+///   printed.
+/// - `#[imm("..")]` maps the raw immediate field of the format, from its msb to its lsb, to the
+///   bits of the real immediate in the notation of the RISC-V spec, e.g. `"5|4:2|7:6"`. The
+///   immediate is unsigned unless the layout ends with `|sext`, as in `"9|4|6|8:7|5|sext"`.
+/// - `#[priority(n)]` orders overlapping encodings, higher first, 0 by default. Ties go to the
+///   mask fixing more bits, then to the name, except between instructions of different
+///   `#[xlen]`, which decode as `Error::Ambiguous` in a map not built for one XLEN.
+/// - `#[extension("M")]` and `#[xlen(32)]`/`#[xlen(64)]` tag the instruction for
+///   `DecoderBuilder`.
+/// - `#[reserved("..")]` marks encodings of the instruction that decode as `Error::Reserved`.
+/// - `#[class(..)]` classifies the instruction as one of `InsnClass`, `#[class(return)]` gives
+///   `InsnClass::Return`. Without it the opcode tells branches, `jal`/`jalr`, which are calls
+///   when linking to `x1`, fences, SYSTEM and csr instructions, `c.beqz`/`c.bnez`,
+///   `c.jr`/`c.jalr`/`c.ebreak` and `c.jal`, then B format instructions default to `Branch`, J and
+///   CJ ones to `Jump` and the others to `Other`. A code covering SYSTEM or `c.jr` encodings of
+///   different classes needs the attribute.
+/// - `#[reads("..")]`/`#[writes("..")]` replace the default operands with
+///   `[x|f|v:](rd|rs1|rs2)` fields, fixed registers like `x1` and `csr` for the csr number in
+///   imm. By default rd is written and rs1/rs2 are read, except for the compressed formats, whose
+///   operands follow the opcode: `c.li` reads nothing, `c.lwsp` and `c.swsp` read `x2`, `c.jr`
+///   writes nothing and `c.jal` writes `x1`. CI instructions of an opcode not known need both
+///   attributes.
+///
+/// Named fields are filled from the format by `from_ir` and `decode_owned`, `imm` is sign
+/// extended only if the format or the `#[imm]` layout is signed. The instance held by the map has
+/// all fields 0. Fields must be operands of the format.
+/// ```compile_fail
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::Instruction;
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// #[derive(Instruction)]
+/// #[format(U)]
+/// #[code("32b?????????????????????????_0110111")]
+/// struct Lui {
+///     rd: u8,
+///     rs1: u8,
+/// }
+/// impl Execution for Lui {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+/// }
+/// ```
+///
+#[proc_macro_derive(
+    Instruction,
+    attributes(
+        code, format, asm, imm, priority, extension, xlen, reserved, class, reads, writes
+    )
+)]
 pub fn instruction(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).unwrap();
     let name = &ast.ident;
@@ -349,3 +420,324 @@ pub fn define_csr(input: TokenStream) -> TokenStream {
 pub fn csr_map(input: TokenStream) -> TokenStream {
     csr::csr_map::expand(parse_macro_input!(input)).into()
 }
+
+/// Builds `GlobalInsnMap` for `terminus_insn!(.., Static)` from every listed instruction, with the
+/// decoder dispatching on the low 7 bits of a word through a table const-evaluated from their
+/// `CODE`/`MASK`, down to the candidates in precedence order. It has to be invoked in the module
+/// deriving the instructions, and overlapping encodings that neither `#[priority]` nor mask
+/// specificity can order are rejected at compile time.
+/// # Example
+/// ```rust
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{isa, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception, Static);
+/// #[derive(Instruction)]
+/// #[format(I)]
+/// #[code("32b?????????????????_000_?????_0010011")]
+/// struct Addi();
+/// impl Execution for Addi {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+/// #[derive(Instruction)]
+/// #[format(R)]
+/// #[code("32b0000000_?????_?????_000_?????_0110011")]
+/// struct Add();
+/// impl Execution for Add {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+/// isa! { Addi, Add }
+///
+/// fn main() {
+///   assert_eq!(GDECODER.iter().count(), 2);
+///   assert_eq!(GDECODER.disasm(&0x00150513), "Addi");
+///   assert_eq!(GDECODER.disasm(&0x003100b3), "Add");
+///   assert_eq!(GDECODER.decode(&0x403100b3).err(), Some(Error::Illegal(0x403100b3)));
+/// # }
+/// ```
+/// ```compile_fail
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{isa, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception, Static);
+/// #[derive(Instruction)]
+/// #[format(I)]
+/// #[code("32b?????????????????_000_?????_0010011")]
+/// struct Addi();
+/// impl Execution for Addi {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+/// #[derive(Instruction)]
+/// #[format(I)]
+/// #[code("32b????????????????0_?00_?????_0010011")]
+/// struct AddiHint();
+/// impl Execution for AddiHint {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+/// isa! { Addi, AddiHint }
+/// # fn main() {}
+/// ```
+#[proc_macro]
+pub fn isa(input: TokenStream) -> TokenStream {
+    isa::expand(parse_macro_input!(input)).into()
+}
+
+/// Generates a `#[derive(Instruction)]` unit struct for every instruction listed in the
+/// riscv-opcodes files given relative to the crate directory, leaving the `Execution` impls to the
+/// caller. Struct names are camel-cased mnemonics, `c.addi` becomes `CAddi`, while `#[asm]` keeps
+/// the mnemonic and lists the register and immediate operands in the order of the file, a split
+/// immediate at its last part, as in `beq {rs1}, {rs2}, {imm}`. The format is inferred from the
+/// operand names, and `#[extension]`/`#[xlen]` come from the file name, `rv64_i` gives extension
+/// "I" for XLEN 64. Operands required to be nonzero, like `rd_n0` or `c_nzimm6hi`/`c_nzimm6lo`,
+/// make their zero encodings `#[reserved]`. `$pseudo_op` lines are skipped with a warning naming
+/// the line and `$import` lines are rejected.
+/// # Example
+/// ```rust
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{include_opcodes, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// include_opcodes!("opcodes/rv_i", "opcodes/rv64_i", "opcodes/rv_c");
+/// macro_rules! nop_execution {
+///     ($($name:ident),*) => {
+///         $(impl Execution for $name {
+///             fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///                 Ok(())
+///             }
+///         })*
+///     };
+/// }
+/// nop_execution!(Lui, Jal, Beq, Lw, Sw, Addi, Add, Ecall, Addiw);
+/// nop_execution!(CAddi4spn, CLw, CSw, CNop, CAddi, CLi, CAndi, CSub, CJ, CBeqz, CLwsp, CMv, CSwsp);
+///
+/// fn main() {
+///   assert_eq!(Add::CODE, 0x33);
+///   assert_eq!(Add::MASK, 0xfe00707f);
+///   assert_eq!(Ecall::MASK, 0xffffffff);
+///   assert_eq!(GDECODER.disasm(&0x00150513), "addi x10, x10, 1");
+///   assert_eq!(GDECODER.disasm(&0x0001), "c.nop 0");
+///   assert_eq!(GDECODER.disasm(&0x0505), "c.addi x10, 1");
+///   assert_eq!(GDECODER.disasm(&0x00a12423), "sw x2, x10, 8");
+///   assert_eq!(GDECODER.disasm(&0x00b50463), "beq x10, x11, 8");
+///   assert_eq!(GDECODER.disasm(&0x00000073), "ecall");
+///   assert_eq!(assemble("c.addi a0, 1\naddi a0, a0, 1").unwrap(), vec![0x0505, 0x00150513]);
+///   let infos = GDECODER.infos();
+///   let info = |name: &str| infos.iter().find(|i| i.name == name).unwrap().clone();
+///   assert_eq!((info("Beq").format, info("Sw").format, info("Jal").format), ("B", "S", "J"));
+///   assert_eq!((info("CSub").format, info("CAndi").format, info("CSwsp").format), ("CA", "CB", "CSS"));
+///   assert_eq!((info("CAddi4spn").format, info("CLw").format, info("CMv").format), ("CIW", "CL", "CR"));
+///   assert_eq!((info("Addiw").extension, info("Addiw").xlen), ("I", 64));
+///   assert_eq!((info("CJ").extension, info("CJ").xlen), ("C", 0));
+///   assert_eq!(GDECODER.decode(&0x0000).err(), Some(Error::Reserved(0x0000, "CAddi4spn".to_string())));
+///   assert_eq!(GDECODER.decode(&0x0501).err(), Some(Error::Reserved(0x0501, "CAddi".to_string())));
+///   assert_eq!(GDECODER.decode(&0x4002).err(), Some(Error::Reserved(0x4002, "CLwsp".to_string())));
+/// # }
+/// ```
+/// ```compile_fail
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{include_opcodes, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// include_opcodes!("opcodes/rv_zimport");
+/// # fn main() {}
+/// ```
+#[proc_macro]
+pub fn include_opcodes(input: TokenStream) -> TokenStream {
+    match opcodes::expand(parse_macro_input!(input)) {
+        Ok(token_stream) => token_stream.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Generates a `#[derive(Instruction)]` unit struct and its `Format` impl for every pattern of a
+/// QEMU decodetree file given relative to the crate directory, leaving the `Execution` impls to
+/// the caller. The insn width is 32 unless given as the second argument. Fields, argument sets,
+/// formats and patterns are supported. Arguments named `rd`, `rs1`, `rs2` and `imm` back the
+/// `Format` methods of the same name, a `!function=f` field is decoded through a `fn f(v) -> v`
+/// in scope and encoded through its inverse `fn f_inv(v) -> v`, and the patterns of a `{}`
+/// overlap group get decreasing `#[priority]` so the first match wins as in decodetree.
+/// # Example
+/// ```rust
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{include_decodetree, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// fn ex_shift_1(v: TerminusInsnT) -> TerminusInsnT {
+///     v << 1
+/// }
+/// fn ex_shift_1_inv(v: TerminusInsnT) -> TerminusInsnT {
+///     v >> 1
+/// }
+/// fn ex_shift_12(v: TerminusInsnT) -> TerminusInsnT {
+///     v << 12
+/// }
+/// fn ex_shift_12_inv(v: TerminusInsnT) -> TerminusInsnT {
+///     v >> 12
+/// }
+/// include_decodetree!("decodetree/rv32.decode");
+/// macro_rules! nop_execution {
+///     ($($name:ident),*) => {
+///         $(impl Execution for $name {
+///             fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///                 Ok(())
+///             }
+///         })*
+///     };
+/// }
+/// nop_execution!(Lui, Beq, Sw, AddiHint, Addi, Add, Ecall);
+///
+/// fn main() {
+///   assert_eq!(GDECODER.disasm(&0x00150513), "Addi");
+///   assert_eq!(GDECODER.disasm(&0x00100013), "AddiHint");
+///   assert_eq!(GDECODER.disasm(&0x00000073), "Ecall");
+///   let insn = GDECODER.decode(&0xfff50513).unwrap();
+///   assert_eq!((insn.rd(&0xfff50513), insn.rs1(&0xfff50513), insn.imm(&0xfff50513)), (10, 10, 0xffffffff));
+///   let insn = GDECODER.decode(&0xff450513).unwrap();
+///   assert_eq!(insn.imm_signed(&0xff450513), -12);
+///   let insn = GDECODER.decode(&0xfe000ee3).unwrap();
+///   assert_eq!((insn.imm(&0xfe000ee3), insn.imm_signed(&0xfe000ee3)), (-4i32 as u32, -4));
+///   let insn = GDECODER.decode(&0x00a12423).unwrap();
+///   assert_eq!(insn.imm_signed(&0x00a12423), 8);
+///   let insn = GDECODER.decode(&0x123452b7).unwrap();
+///   assert_eq!((insn.rd(&0x123452b7), insn.imm(&0x123452b7)), (5, 0x12345000));
+///   assert_eq!(Sw::encode(0, 2, 10, 8), 0x00a12423);
+///   assert_eq!(Beq::encode(0, 0, 0, -4i32 as u32), 0xfe000ee3);
+///   assert_eq!(Lui::encode(5, 0, 0, 0x12345000), 0x123452b7);
+///   assert!(GDECODER.ambiguities().is_empty());
+/// # }
+/// ```
+/// A pattern name given twice is rejected at its second line.
+/// ```compile_fail
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{include_decodetree, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// include_decodetree!("decodetree/repeated.decode");
+/// # fn main() {}
+/// ```
+#[proc_macro]
+pub fn include_decodetree(input: TokenStream) -> TokenStream {
+    match decodetree::expand(parse_macro_input!(input)) {
+        Ok(token_stream) => token_stream.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Defines a unit struct implementing `Format` that `#[format(..)]` accepts by name. Every field
+/// of `rd`, `rs1`, `rs2`, `imm` and `op` is given as `msb:lsb` or single bit segments joined by
+/// `|`, concatenated msb first. `imm` marked `sext` is sign extended by `imm_sext` and printed
+/// signed, otherwise it is unsigned. Only `rd` and `rs1` may share bits.
+/// # Example
+/// ```rust
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::{define_format, Instruction};
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// define_format!(Mac { rd: 11:7, rs1: 19:15, imm: 31:20 | sext });
+/// define_format!(Store { rs1: 19:15, rs2: 24:20, imm: 31:25 | 11:7 });
+/// #[derive(Instruction)]
+/// #[format(Mac)]
+/// #[code("32b?????????????????_000_?????_0001011")]
+/// #[asm("mac {rd}, {rs1}, {imm}")]
+/// struct MacI {
+///     rd: u8,
+///     imm: i32,
+/// }
+/// impl Execution for MacI {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+/// #[derive(Instruction)]
+/// #[format(Store)]
+/// #[code("32b???????_?????_?????_001_?????_0001011")]
+/// #[asm("mst {rs2}, {imm}({rs1})")]
+/// struct Mst();
+/// impl Execution for Mst {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+///   assert_eq!(GDECODER.disasm(&0xfff5050b), "mac x10, x10, -1");
+///   assert_eq!(GDECODER.decode(&0xfff5050b).unwrap().imm_sext(&0xfff5050b), 0xffffffff);
+///   assert_eq!((Mac.imm_len(), Mac.imm_unsigned()), (12, false));
+///   assert_eq!((Store.imm_len(), Store.imm_unsigned()), (12, true));
+///   let mac = MacI::from_ir(&0x8005050b);
+///   assert_eq!((mac.rd, mac.imm), (10, -2048));
+///   assert_eq!(Mst::encode(0, 2, 10, 8), 0x00a1140b);
+///   assert_eq!(GDECODER.disasm(&0x00a1140b), "mst x10, 8(x2)");
+///   assert_eq!(GDECODER.disasm(&0xfea1100b), "mst x10, 4064(x2)");
+///   let infos = GDECODER.infos();
+///   assert_eq!((infos[0].format, infos[0].fields), ("Mac", &["rd", "rs1", "imm"][..]));
+///   assert_eq!((infos[1].format, infos[1].fields), ("Store", &["rs1", "rs2", "imm"][..]));
+/// # }
+/// ```
+/// A type not defined by `define_format!` is reported at its name as neither a built-in format nor
+/// a `define_format!` type.
+/// ```compile_fail,E0277
+/// #
+/// extern crate terminus_macros;
+/// extern crate terminus_proc_macros;
+/// use terminus_macros::*;
+/// use terminus_proc_macros::Instruction;
+/// pub struct Processor;
+/// pub struct Exception;
+/// terminus_insn!(u32, Processor, Exception);
+/// #[derive(Instruction)]
+/// #[format(Processor)]
+/// #[code("32b?????????????????_000_?????_0001011")]
+/// struct MacI();
+/// impl Execution for MacI {
+///     fn execute(&self, p: &mut Processor)->Result<(), Exception> {
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+/// }
+/// ```
+#[proc_macro]
+pub fn define_format(input: TokenStream) -> TokenStream {
+    match format::expand(parse_macro_input!(input)) {
+        Ok(token_stream) => token_stream.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,358 @@
+use proc_macro2::{Span, TokenStream};
+use regex::Regex;
+use syn::parse::{Error, Parse, ParseStream, Result};
+use syn::punctuated::Punctuated;
+use syn::{Ident, LitStr, Token};
+
+pub struct Opcodes {
+    files: Punctuated<LitStr, Token![,]>,
+}
+
+impl Parse for Opcodes {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Opcodes {
+            files: input.parse_terminated(|input| input.parse::<LitStr>())?,
+        })
+    }
+}
+
+struct Opcode {
+    name: String,
+    args: Vec<String>,
+    bits: Vec<char>,
+    reserved: Vec<Vec<char>>,
+}
+
+pub fn expand(opcodes: Opcodes) -> Result<TokenStream> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    let mut tokens = vec![];
+    for file in opcodes.files.iter() {
+        let path = std::path::Path::new(&dir).join(file.value());
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            Error::new(
+                file.span(),
+                format!("can not read \"{}\": {}!", path.display(), e),
+            )
+        })?;
+        let (extension, xlen) = parse_file_name(file)?;
+        let path = path.to_string_lossy().to_string();
+        tokens.push(quote!(
+            const _: &str = include_str!(#path);
+        ));
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let error =
+                |msg: &str| Error::new(file.span(), format!("{}:{}: {}", file.value(), i + 1, msg));
+            if line.is_empty() {
+                continue;
+            }
+            //a pseudo op aliases an encoding of an instruction defined anyway, an import pulls in
+            //instructions that would silently be missing
+            if line.starts_with("$pseudo_op") {
+                tokens.push(expand_warning(
+                    file,
+                    i + 1,
+                    &format!("{}:{}: `$pseudo_op` line skipped", file.value(), i + 1),
+                ));
+                continue;
+            }
+            if line.starts_with('$') {
+                return Err(error(&format!(
+                    "\"{}\" is not supported, include the file defining the instruction instead!",
+                    line
+                )));
+            }
+            let opcode = parse_opcode(line).map_err(|msg| error(&msg))?;
+            tokens.push(expand_opcode(&opcode, &extension, xlen));
+        }
+    }
+    Ok(quote!(#(#tokens)*))
+}
+
+//"rv64_i" is extension "I" for xlen 64, "rv_zicsr" is extension "Zicsr" for every xlen
+fn parse_file_name(file: &LitStr) -> Result<(String, usize)> {
+    lazy_static! {
+        static ref FILE_NAME: Regex = Regex::new("^rv(32|64|128)?_([a-z0-9_]+)$").unwrap();
+    }
+    let value = file.value();
+    let name = std::path::Path::new(&value)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Some(caps) = FILE_NAME.captures(&name) {
+        let xlen = caps
+            .get(1)
+            .map_or(0, |m| m.as_str().parse::<usize>().unwrap_or(0));
+        let mut extension = caps[2].to_string();
+        extension[..1].make_ascii_uppercase();
+        Ok((extension, xlen))
+    } else {
+        Err(Error::new(
+            file.span(),
+            format!(
+                "invalid opcodes file name \"{}\", valid format is ^rv(32|64|128)?_[a-z0-9_]+$!",
+                name
+            ),
+        ))
+    }
+}
+
+fn parse_opcode(line: &str) -> std::result::Result<Opcode, String> {
+    lazy_static! {
+        static ref FIELD: Regex =
+            Regex::new("^([0-9]+)(?:\\.\\.([0-9]+))?=(0x[0-9a-fA-F]+|[0-9]+)$").unwrap();
+    }
+    let mut tokens = line.split_whitespace();
+    let name = tokens.next().unwrap_or("").to_string();
+    let mut args = vec![];
+    let mut fields = vec![];
+    for token in tokens {
+        if !token.contains('=') {
+            args.push(token.to_string());
+            continue;
+        }
+        let caps = FIELD
+            .captures(token)
+            .ok_or_else(|| format!("invalid field \"{}\"!", token))?;
+        let msb = caps[1].parse::<usize>().map_err(|e| e.to_string())?;
+        let lsb = caps
+            .get(2)
+            .map_or(Ok(msb), |m| m.as_str().parse::<usize>())
+            .map_err(|e| e.to_string())?;
+        let value = if let Some(hex) = caps[3].strip_prefix("0x") {
+            u128::from_str_radix(hex, 16)
+        } else {
+            caps[3].parse::<u128>()
+        }
+        .map_err(|e| e.to_string())?;
+        if msb < lsb || msb >= 32 || value >> (msb - lsb + 1) != 0 {
+            return Err(format!("invalid field \"{}\"!", token));
+        }
+        fields.push((msb, lsb, value));
+    }
+    let len = if fields
+        .iter()
+        .any(|(msb, lsb, v)| *msb == 1 && *lsb == 0 && *v == 3)
+    {
+        32
+    } else {
+        16
+    };
+    let mut bits = vec!['?'; len];
+    for (msb, lsb, value) in fields {
+        for bit in lsb..=msb {
+            if bit >= len || bits[len - 1 - bit] != '?' {
+                return Err(format!(
+                    "bit {} of \"{}\" is invalid or defined more than once!",
+                    bit, name
+                ));
+            }
+            bits[len - 1 - bit] = if (value >> (bit - lsb)) & 1 == 1 {
+                '1'
+            } else {
+                '0'
+            };
+        }
+    }
+    let reserved = parse_constraints(&name, &args, len)?;
+    Ok(Opcode {
+        name,
+        args,
+        bits,
+        reserved,
+    })
+}
+
+//bits of the operands riscv-opcodes requires to be nonzero, like rd_n0 or c_nzimm6hi/c_nzimm6lo
+fn nonzero_bits(arg: &str) -> Option<(usize, usize)> {
+    match arg {
+        "rd_n0" | "rs1_n0" | "rd_rs1_n0" | "c_rs1_n0" | "rd_n2" => Some((11, 7)),
+        "c_rs2_n0" | "c_nzuimm5" => Some((6, 2)),
+        "c_nzuimm10" => Some((12, 5)),
+        _ if arg.starts_with("c_nz") && arg.ends_with("hi") => Some((12, 12)),
+        _ if arg.starts_with("c_nz") && arg.ends_with("lo") => Some((6, 2)),
+        _ => None,
+    }
+}
+
+//the encodings the constraints leave reserved: a zero register operand, rd_n2 also excludes x2,
+//and all parts of a nonzero immediate being zero. c.nop is exempt, riscv-opcodes names its hint
+//immediate nonzero although a zero one is the canonical nop.
+fn parse_constraints(
+    name: &str,
+    args: &[String],
+    len: usize,
+) -> std::result::Result<Vec<Vec<char>>, String> {
+    let pattern = |fields: &[(usize, usize, u128)]| {
+        let mut bits = vec!['?'; len];
+        for &(msb, lsb, value) in fields {
+            for bit in lsb..=msb.min(len - 1) {
+                bits[len - 1 - bit] = if (value >> (bit - lsb)) & 1 == 1 {
+                    '1'
+                } else {
+                    '0'
+                };
+            }
+        }
+        bits
+    };
+    let mut reserved = vec![];
+    let mut imm = vec![];
+    for arg in args {
+        match nonzero_bits(arg) {
+            Some((msb, lsb)) if arg.starts_with("c_nz") => imm.push((msb, lsb, 0)),
+            Some((msb, lsb)) => {
+                reserved.push(pattern(&[(msb, lsb, 0)]));
+                if arg == "rd_n2" {
+                    reserved.push(pattern(&[(msb, lsb, 2)]));
+                }
+            }
+            None if arg.contains("_n0") || arg.contains("_n2") || arg.starts_with("c_nz") => {
+                return Err(format!("unsupported constraint of operand \"{}\"!", arg))
+            }
+            None => {}
+        }
+    }
+    if !imm.is_empty() && name != "c.nop" {
+        reserved.push(pattern(&imm));
+    }
+    Ok(reserved)
+}
+
+fn infer_format(opcode: &Opcode) -> &'static str {
+    let has = |prefix: &str| opcode.args.iter().any(|a| a.starts_with(prefix));
+    if opcode.bits.len() == 32 {
+        if has("jimm20") {
+            "J"
+        } else if has("imm20") {
+            "U"
+        } else if has("bimm12") {
+            "B"
+        } else if has("imm12hi") {
+            "S"
+        } else if has("rs2") || has("rs3") {
+            "R"
+        } else {
+            "I"
+        }
+    } else if has("c_imm12") {
+        "CJ"
+    } else if has("c_bimm9") {
+        "CB"
+    } else if has("rd_rs1_p") && has("rs2_p") {
+        "CA"
+    } else if has("rd_rs1_p") {
+        "CB"
+    } else if has("rs1_p") && has("rs2_p") {
+        "CS"
+    } else if has("rd_p") && has("rs1_p") {
+        "CL"
+    } else if has("rd_p") {
+        "CIW"
+    } else if has("c_rs2") && (has("c_uimm8sp_s") || has("c_uimm9sp_s") || has("c_uimm10sp_s")) {
+        "CSS"
+    } else if opcode.args.iter().all(|a| !a.contains("imm")) {
+        "CR"
+    } else {
+        "CI"
+    }
+}
+
+//"c.addi" is "CAddi", "fcvt.s.w" is "FcvtSW"
+pub fn struct_name(name: &str) -> String {
+    name.split(['.', '_'])
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut s = s.to_string();
+            s[..1].make_ascii_uppercase();
+            s
+        })
+        .collect()
+}
+
+//the asm template keeps the name and the operand order of riscv-opcodes, the parts of a split
+//immediate are one {imm} at the last of them and operands that are neither a register nor an
+//immediate are left out
+fn asm_template(opcode: &Opcode) -> String {
+    let operand = |arg: &str| {
+        if arg.starts_with("rd") {
+            Some("{rd}")
+        } else if arg.starts_with("rs1") || arg.starts_with("c_rs1") {
+            Some("{rs1}")
+        } else if arg.starts_with("rs2") || arg.starts_with("c_rs2") {
+            Some("{rs2}")
+        } else if arg.contains("uimm") {
+            Some("{imm:u}")
+        } else if arg.contains("imm") {
+            Some("{imm}")
+        } else {
+            None
+        }
+    };
+    let operands = opcode
+        .args
+        .iter()
+        .filter_map(|a| operand(a))
+        .collect::<Vec<_>>();
+    let operands = operands
+        .iter()
+        .enumerate()
+        .filter(|(i, op)| !op.starts_with("{imm") || !operands[i + 1..].contains(op))
+        .map(|(_, op)| *op)
+        .collect::<Vec<_>>();
+    if operands.is_empty() {
+        opcode.name.clone()
+    } else {
+        format!("{} {}", opcode.name, operands.join(", "))
+    }
+}
+
+fn expand_opcode(opcode: &Opcode, extension: &str, xlen: usize) -> TokenStream {
+    let name = Ident::new(&struct_name(&opcode.name), Span::call_site());
+    let format = Ident::new(infer_format(opcode), Span::call_site());
+    let code = format!(
+        "{}b{}",
+        opcode.bits.len(),
+        opcode.bits.iter().collect::<String>()
+    );
+    let xlen = if xlen == 0 {
+        quote!()
+    } else {
+        let xlen = syn::LitInt::new(&xlen.to_string(), Span::call_site());
+        quote!(#[xlen(#xlen)])
+    };
+    let asm = asm_template(opcode);
+    let reserved = opcode.reserved.iter().map(|bits| {
+        let bits = format!("{}b{}", bits.len(), bits.iter().collect::<String>());
+        quote!(#[reserved(#bits)])
+    });
+    quote!(
+        #[derive(Instruction)]
+        #[format(#format)]
+        #[code(#code)]
+        #[asm(#asm)]
+        #[extension(#extension)]
+        #xlen
+        #(#reserved)*
+        pub struct #name();
+    )
+}
+
+//rustc has no stable way for a proc macro to warn, so msg is attached to a deprecated item that
+//is used right away
+fn expand_warning(file: &LitStr, line: usize, msg: &str) -> TokenStream {
+    let ident = Ident::new(
+        &format!(
+            "__TERMINUS_OPCODES_{}_{}",
+            struct_name(&file.value().replace(['/', '\\', '-'], "_")).to_uppercase(),
+            line
+        ),
+        file.span(),
+    );
+    quote!(
+        #[deprecated(note = #msg)]
+        #[allow(non_upper_case_globals)]
+        const #ident: () = ();
+        const _: () = #ident;
+    )
+}
@@ -0,0 +1,90 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(B)]
+#[code("32b???????_?????_?????_000_?????_1100011")]
+struct Beq();
+
+#[derive(Instruction)]
+#[format(CR)]
+#[code("16b1000_?????_00000_10")]
+struct CJr();
+
+nop_execution!(Addi, CAddi, Beq, CJr);
+
+const BASE: u64 = 0x80000000;
+
+fn fetch(mem: &[u16]) -> impl FnMut(u64) -> Option<u16> {
+    let mem = mem.to_vec();
+    move |pc: u64| mem.get(((pc - BASE) / 2) as usize).copied()
+}
+
+#[test]
+fn blocks() {
+    //addi a0, a0, 1; c.addi a1, 1; beq a0, a1, 0; addi a2, a2, 1
+    let mut mem: Vec<u16> = vec![0x0513, 0x0015, 0x0585, 0x0063, 0x00b5, 0x0613, 0x0016];
+    let mut cache = BlockCache::new(&*GDECODER);
+    {
+        let block = cache.get(BASE, fetch(&mem)).unwrap();
+        assert_eq!(block.end, BASE + 10);
+        let insns = block
+            .insns
+            .iter()
+            .map(|i| (i.pc, i.ir, i.insn.name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            insns,
+            vec![
+                (BASE, 0x00150513, "Addi"),
+                (BASE + 4, 0x0585, "CAddi"),
+                (BASE + 6, 0x00b50063, "Beq")
+            ]
+        );
+    }
+    assert_eq!(cache.get(BASE + 10, fetch(&mem)).unwrap().insns.len(), 1);
+    assert_eq!(cache.len(), 2);
+
+    //c.addi a1, 1 becomes c.addi a1, 2
+    mem[2] = 0x0589;
+    cache.invalidate(BASE + 4, 2);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get(BASE, fetch(&mem)).unwrap().insns[1].ir, 0x0589);
+    cache.fence_i();
+    assert!(cache.is_empty());
+
+    assert_eq!(
+        cache.get(BASE + 14, fetch(&mem)).err(),
+        Some(Error::Truncated(0, 2))
+    );
+    mem[0] = 0xffff;
+    assert_eq!(
+        cache.get(BASE, fetch(&mem)).err(),
+        Some(Error::Illegal(0xffff))
+    );
+}
+
+#[test]
+fn indirect_jump_ends_block() {
+    //c.addi a1, 1; c.jr a0; c.addi a1, 1
+    let mem: Vec<u16> = vec![0x0585, 0x8502, 0x0585];
+    let mut cache = BlockCache::new(&*GDECODER);
+    let block = cache.get(BASE, fetch(&mem)).unwrap();
+    assert_eq!(block.end, BASE + 4);
+    assert_eq!(block.insns.len(), 2);
+}
@@ -0,0 +1,101 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(B)]
+#[code("32b?????????????????_000_?????_1100011")]
+struct Beq();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b000000000000_00001_000_00000_1100111")]
+#[class(return)]
+struct Ret();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_010_?????_0000011")]
+#[class(load)]
+struct Lw();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_1100111")]
+struct Jalr();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b000000000000_00000_000_00000_1110011")]
+struct Ecall();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_001_?????_1110011")]
+struct Csrrw();
+
+#[derive(Instruction)]
+#[format(CR)]
+#[code("16b1000_?????_00000_10")]
+struct CJr();
+
+#[derive(Instruction)]
+#[format(CR)]
+#[code("16b1001_?????_00000_10")]
+struct CJalr();
+
+#[derive(Instruction)]
+#[format(CB)]
+#[code("16b110_???_???_?????_01")]
+struct CBeqz();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b001_???????????_01")]
+struct CJal();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b101_???????????_01")]
+struct CJ();
+
+#[derive(Instruction)]
+#[format(J)]
+#[code("32b????????????????????_00001_1101111")]
+struct Call();
+
+nop_execution!(Beq, Ret, Lw, Jalr, Ecall, Csrrw, CJr, CJalr, CBeqz, CJal, CJ, Call);
+
+#[test]
+fn classes() {
+    assert_eq!(
+        GDECODER.decode(&0x00b50463).unwrap().class(),
+        InsnClass::Branch
+    );
+    assert_eq!(
+        GDECODER.decode(&0x00008067).unwrap().class(),
+        InsnClass::Return
+    );
+    assert_eq!(
+        GDECODER.decode(&0x00052503).unwrap().class(),
+        InsnClass::Load
+    );
+    for (ir, class) in [
+        (0x00050067, InsnClass::Jump),
+        (0x00000073, InsnClass::System),
+        (0x30051573, InsnClass::Csr),
+        (0x8502, InsnClass::Jump),
+        (0x9502, InsnClass::Call),
+        (0xc101, InsnClass::Branch),
+        (0x2001, InsnClass::Call),
+        (0xa001, InsnClass::Jump),
+        (0x000000ef, InsnClass::Call),
+    ] {
+        assert_eq!(GDECODER.decode(&ir).unwrap().class(), class);
+    }
+    assert!(GDECODER.infos().iter().all(|i| i.class != InsnClass::Other));
+}
@@ -0,0 +1,34 @@
+#![allow(dead_code)]
+
+//fixture shared by the integration tests, each test crate invokes terminus_insn! itself so it
+//gets a registry of its own
+
+#[derive(Default)]
+pub struct Processor {
+    pub ir: u32,
+    pub x: [u64; 32],
+}
+
+pub struct Exception;
+
+//xorshift64 with a fixed seed
+pub fn rng() -> impl FnMut() -> u64 {
+    let mut state = 0x2545f4914f6cdd1du64;
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! nop_execution {
+    ($($name:ident),*) => {
+        $(impl Execution for $name {
+            fn execute(&self, _: &mut Processor) -> Result<(), Exception> {
+                Ok(())
+            }
+        })*
+    };
+}
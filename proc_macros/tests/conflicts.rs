@@ -0,0 +1,42 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception, Simple);
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_0_00000_00000_01")]
+struct CNop();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b010_?_?????_?????_01")]
+struct CLi();
+
+nop_execution!(CAddi, CNop, CLi);
+
+#[test]
+fn check_conflicts() {
+    let conflicts = GDECODER.check_conflicts(ConflictPolicy::Warn);
+    assert_eq!(
+        conflicts,
+        vec![Conflict {
+            name: "CAddi".to_string(),
+            code: 0x1,
+            mask: 0xe003,
+            other_name: "CNop".to_string(),
+            other_code: 0x1,
+            other_mask: 0xffff,
+        }]
+    );
+    assert!(std::panic::catch_unwind(|| GDECODER.check_conflicts(ConflictPolicy::Panic)).is_err());
+    assert!(GDECODER.ambiguities().is_empty());
+}
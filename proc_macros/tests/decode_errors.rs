@@ -0,0 +1,73 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_010_?????_0000011")]
+struct Lw();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b011_?_00010_?????_01")]
+#[reserved("16b011_0_00010_00000_01")]
+#[extension("C")]
+struct CAddi16sp();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0011011")]
+#[xlen(64)]
+struct Addiw();
+
+nop_execution!(Lw, Addi, CAddi16sp, Addiw);
+
+#[test]
+fn reserved_disabled_truncated() {
+    assert_eq!(
+        GDECODER.decode(&0x6101).err(),
+        Some(Error::Reserved(0x6101, "CAddi16sp".to_string()))
+    );
+    assert!(GDECODER.decode(&0x7139).is_ok());
+    let rv32i = DecoderBuilder::new().extensions(&["I"]).build();
+    assert_eq!(
+        rv32i.decode(&0x7139).err(),
+        Some(Error::Disabled(0x7139, "CAddi16sp".to_string()))
+    );
+    let rv32i = DecoderBuilder::new().xlen(32).extensions(&["I"]).build();
+    assert_eq!(
+        rv32i.decode(&0x0015051b).err(),
+        Some(Error::Illegal(0x0015051b))
+    );
+    assert_eq!(
+        rv32i.decode(&0x7139).err(),
+        Some(Error::Disabled(0x7139, "CAddi16sp".to_string()))
+    );
+    assert_eq!(
+        GDECODER.decode_at(&[0x13, 0x05], 0).err(),
+        Some(Error::Truncated(0x0513, 4))
+    );
+}
+
+#[test]
+fn nearest() {
+    let err = GDECODER.decode(&0x00057503).err().unwrap();
+    assert_eq!(err.to_string(), "illegal instruction 0x57503");
+    let nearest = GDECODER.nearest(&err.ir(), 2);
+    assert_eq!(
+        nearest
+            .iter()
+            .map(|(d, i)| (*d, i.name()))
+            .collect::<Vec<_>>(),
+        vec![(2, "Lw".to_string()), (4, "Addi".to_string())]
+    );
+}
@@ -0,0 +1,121 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0110011")]
+#[asm("add {rd}, {rs1}, {rs2}")]
+#[extension("I")]
+struct Add();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+#[asm("c.addi {rd}, {imm:d}")]
+#[imm("5|4:0|sext")]
+#[extension("C")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b0000000_?????_?????_001_?????_0010011")]
+#[asm("slli {rd}, {rs1}, {imm}")]
+#[extension("I")]
+#[xlen(32)]
+struct SlliRv32();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b000000_??????_?????_001_?????_0010011")]
+#[asm("slli {rd}, {rs1}, {imm}")]
+#[extension("I")]
+#[xlen(64)]
+struct SlliRv64();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b101_???????????_01")]
+#[asm("c.j {imm}")]
+#[extension("C")]
+#[xlen(32)]
+struct CJRv32();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b101_???????????_01")]
+#[asm("c.j {imm}")]
+#[extension("C")]
+#[xlen(64)]
+struct CJRv64();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b001_???????????_01")]
+#[asm("c.jal {imm}")]
+#[xlen(32)]
+struct CJal();
+
+nop_execution!(Add, CAddi, SlliRv32, SlliRv64, CJRv32, CJRv64, CJal);
+
+#[test]
+fn instr_dict() {
+    let dict = InstrDict::new(&*GDECODER);
+    assert_eq!(
+        dict.to_yaml(),
+        "\
+'add':
+  encoding: '0000000----------000-----0110011'
+  variable_fields: ['rd', 'rs1', 'rs2']
+  extension: ['rv_i']
+  match: '0x33'
+  mask: '0xfe00707f'
+  format: 'R'
+'c_addi':
+  encoding: '000-----------01'
+  variable_fields: ['rd', 'rs1', 'imm']
+  extension: ['rv_c']
+  match: '0x1'
+  mask: '0xe003'
+  format: 'CI'
+  imm: '5|4:0|sext'
+'c_j':
+  encoding: '101-----------01'
+  variable_fields: ['imm']
+  extension: ['rv32_c', 'rv64_c']
+  match: '0xa001'
+  mask: '0xe003'
+  format: 'CJ'
+'c_jal':
+  encoding: '001-----------01'
+  variable_fields: ['imm']
+  extension: ['rv32']
+  match: '0x2001'
+  mask: '0xe003'
+  format: 'CJ'
+'slli_rv32':
+  encoding: '0000000----------001-----0010011'
+  variable_fields: ['rd', 'rs1', 'imm']
+  extension: ['rv32_i']
+  match: '0x1013'
+  mask: '0xfe00707f'
+  format: 'I'
+'slli_rv64':
+  encoding: '000000-----------001-----0010011'
+  variable_fields: ['rd', 'rs1', 'imm']
+  extension: ['rv64_i']
+  match: '0x1013'
+  mask: '0xfc00707f'
+  format: 'I'
+"
+    );
+    let json = dict.to_json();
+    assert!(json
+        .starts_with("{\n  \"add\": {\n    \"encoding\": \"0000000----------000-----0110011\",\n"));
+    assert!(json.contains("\"c_j\": {\n    \"encoding\": \"101-----------01\",\n    \"variable_fields\": [\"imm\"],\n    \"extension\": [\"rv32_c\", \"rv64_c\"],\n"));
+    assert_eq!(dict.entries()[2].1.len(), 2);
+}
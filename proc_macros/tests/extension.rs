@@ -0,0 +1,34 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0110011")]
+#[extension("I")]
+struct Add();
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000001_?????_?????_000_?????_0110011")]
+#[extension("M")]
+struct Mul();
+
+nop_execution!(Add, Mul);
+
+#[test]
+fn enabled_extensions() {
+    let rv32i = DecoderBuilder::new().extensions(&["I"]).build();
+    assert_eq!(rv32i.disasm(&0x003100b3), "Add");
+    assert_eq!(
+        rv32i.decode(&0x023100b3).err(),
+        Some(Error::Disabled(0x023100b3, "Mul".to_string()))
+    );
+    let rv32im = DecoderBuilder::new().extensions(&["I", "M"]).build();
+    assert_eq!(rv32im.disasm(&0x023100b3), "Mul");
+    assert_eq!(GDECODER.iter().count(), 2);
+}
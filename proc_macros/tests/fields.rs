@@ -0,0 +1,81 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0110011")]
+struct Add {
+    rd: u8,
+    rs1: u8,
+    rs2: u8,
+}
+
+impl Execution for Add {
+    fn execute(&self, p: &mut Processor) -> Result<(), Exception> {
+        p.x[self.rd as usize] = p.x[self.rs1 as usize].wrapping_add(p.x[self.rs2 as usize]);
+        Ok(())
+    }
+}
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+struct Addi {
+    rd: u8,
+    rs1: u8,
+    imm: i32,
+}
+
+impl Execution for Addi {
+    fn execute(&self, p: &mut Processor) -> Result<(), Exception> {
+        p.x[self.rd as usize] = p.x[self.rs1 as usize].wrapping_add(self.imm as i64 as u64);
+        Ok(())
+    }
+}
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b010_?_?????_?????_10")]
+#[imm("5|4:2|7:6")]
+struct CLwsp {
+    rd: u8,
+    imm: i32,
+}
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b001_???????????_01")]
+#[imm("11|4|9:8|10|6|7|3:1|5|sext")]
+struct CJal {
+    imm: i64,
+}
+
+nop_execution!(CLwsp, CJal);
+
+#[test]
+fn from_ir() {
+    let addi = Addi::from_ir(&0xfff50593);
+    assert_eq!((addi.rd, addi.rs1, addi.imm), (11, 10, -1));
+    let lwsp = CLwsp::from_ir(&0x450a);
+    assert_eq!((lwsp.rd, lwsp.imm), (10, 128));
+    assert_eq!(CJal::from_ir(&0x3ffd).imm, -2);
+}
+
+#[test]
+fn decode_owned() {
+    let mut p = Processor::default();
+    let program = [0x00500513, 0xfff50593, 0x00b50633]
+        .iter()
+        .map(|ir| GDECODER.decode_owned(ir).unwrap())
+        .collect::<Vec<_>>();
+    for insn in program.iter() {
+        assert!(insn.execute(&mut p).is_ok());
+    }
+    assert_eq!((p.x[10], p.x[11], p.x[12]), (5, 4, 9));
+    assert!(GDECODER.decode_owned(&0xffffffff).is_err());
+}
@@ -0,0 +1,180 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0110011")]
+#[asm("add {rd}, {rs1}, {rs2}")]
+struct Add();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_010_?????_0000011")]
+#[asm("lw {rd}, {imm}({rs1})")]
+struct Lw();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+#[asm("addi {rd}, {rs1}, {imm}")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(B)]
+#[code("32b???????_?????_?????_000_?????_1100011")]
+struct Beq();
+
+#[derive(Instruction)]
+#[format(U)]
+#[code("32b?????????????????????????_0110111")]
+#[asm("lui {rd}, {imm:x}")]
+struct Lui();
+
+#[derive(Instruction)]
+#[format(U)]
+#[code("32b?????????????????????????_0010111")]
+#[asm("auipc {rd}, {imm}")]
+struct Auipc();
+
+#[derive(Instruction)]
+#[format(B)]
+#[code("32b???????_?????_?????_001_?????_1100011")]
+#[asm("bne {rs1}, {rs2}, {imm}")]
+struct Bne();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+#[asm("c.addi {rd}, {imm}")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b010_?_?????_?????_10")]
+#[imm("5|4:2|7:6")]
+#[asm("c.lwsp {rd}, {imm}(sp)")]
+struct CLwsp();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b011_?_00010_?????_01")]
+#[imm("9|4|6|8:7|5|sext")]
+#[asm("c.addi16sp sp, {imm}")]
+struct CAddi16sp();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b001_???????????_01")]
+#[imm("11|4|9:8|10|6|7|3:1|5|sext")]
+#[asm("c.jal {imm}")]
+struct CJal();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b101_???????????_01")]
+struct CJ();
+
+nop_execution!(Add, Lw, Addi, Beq, Lui, Auipc, Bne, CAddi, CLwsp, CAddi16sp, CJal, CJ);
+
+#[test]
+fn disasm() {
+    assert_eq!(GDECODER.disasm(&0x003100b3), "add x1, x2, x3");
+    assert_eq!(GDECODER.disasm(&0xffc42503), "lw x10, -4(x8)");
+    assert_eq!(GDECODER.disasm(&0xffffffff), ".word 0xffffffff");
+    assert_eq!(GDECODER.disasm(&0x00b50463), "Beq");
+}
+
+#[test]
+fn encode() {
+    let code = Beq::encode(0, 1, 2, (-8i32) as u32);
+    assert_eq!(code, 0xfe208ce3);
+    let insn = GDECODER.decode(&code).unwrap();
+    assert_eq!(
+        (insn.rs1(&code), insn.rs2(&code), insn.imm(&code)),
+        (1, 2, 0x1ff8)
+    );
+    assert_eq!(CJ::encode(0, 0, 0, 0x7ff), 0xbffd);
+}
+
+#[test]
+fn assemble_program() {
+    let program = assemble(
+        "
+        loop:
+          addi a0, a0, -1  # count down
+          bne x10, zero, loop
+          .word 0xdeadbeef
+        ",
+    )
+    .unwrap();
+    assert_eq!(program, vec![0xfff50513, 0xfe051ee3, 0xdeadbeef]);
+    assert_eq!(GDECODER.disasm(&program[1]), "bne x10, x0, -4");
+    let err = assemble("addi a0, a0, 4096").err().unwrap();
+    assert_eq!(err.line, 1);
+    assert!(assemble("sub a0, a0, a1").is_err());
+}
+
+#[test]
+fn upper_immediate() {
+    assert_eq!(Lui::encode(10, 0, 0, 0x12345000), 0x12345537);
+    assert_eq!(Lui().imm(&0x12345537), 0x12345000);
+    assert_eq!(GDECODER.disasm(&0x12345537), "lui x10, 0x12345");
+    assert_eq!(assemble("lui a0, 0x12345").unwrap(), vec![0x12345537]);
+    assert_eq!(GDECODER.disasm(&0xfffff517), "auipc x10, -1");
+    assert_eq!(assemble("auipc a0, -1").unwrap(), vec![0xfffff517]);
+    assert_eq!(assemble("auipc a0, 0xfffff").unwrap(), vec![0xfffff517]);
+    assert!(assemble("lui a0, 0x100000").is_err());
+}
+
+#[test]
+fn imm_layout() {
+    let insn = GDECODER.decode(&0x4532).unwrap();
+    assert_eq!(insn.imm_value(&0x4532), 12);
+    assert_eq!(GDECODER.disasm(&0x4532), "c.lwsp x10, 12(sp)");
+    assert_eq!(CLwsp::encode(10, 0, 0, 12), 0x4532);
+    assert_eq!(GDECODER.disasm(&0x450a), "c.lwsp x10, 128(sp)");
+    assert_eq!(GDECODER.decode(&0x450a).unwrap().imm_sext(&0x450a), 128);
+    assert_eq!(assemble("c.lwsp a0, 128(sp)").unwrap(), vec![0x450a]);
+    assert!(assemble("c.lwsp a0, -128(sp)").is_err());
+    let insn = GDECODER.decode(&0x7139).unwrap();
+    assert_eq!(insn.imm_sext(&0x7139) as i32, -64);
+    assert_eq!(CJal::encode(0, 0, 0, (-2i32) as u32), 0x3ffd);
+    assert_eq!(GDECODER.disasm(&0x3ffd), "c.jal -2");
+    assert_eq!(assemble("c.addi16sp sp, -64").unwrap(), vec![0x7139]);
+}
+
+#[test]
+fn decode_stream() {
+    assert_eq!(insn_len(0x0505), Some(2));
+    assert_eq!(insn_len(0x0513), Some(4));
+    assert_eq!(insn_len(0x001f), Some(6));
+    assert_eq!(insn_len(0x003f), Some(8));
+    let bytes = [
+        0x05, 0x05, 0x13, 0x05, 0x15, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05,
+    ];
+    let decoded = GDECODER.decode_at(&bytes, 2).unwrap();
+    assert_eq!((decoded.ir, decoded.len), (0x00150513, 4));
+    let stream = GDECODER.decode_stream(&bytes).collect::<Vec<_>>();
+    assert_eq!(stream.len(), 4);
+    let listing = stream[..2]
+        .iter()
+        .map(|r| {
+            let d = r.as_ref().unwrap();
+            (d.offset, d.insn.disasm(&d.ir))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        listing,
+        vec![
+            (0, "c.addi x10, 1".to_string()),
+            (2, "addi x10, x10, 1".to_string())
+        ]
+    );
+    assert_eq!(stream[2].as_ref().err(), Some(&Error::Illegal(0x1f)));
+    assert_eq!(stream[3].as_ref().err(), Some(&Error::Truncated(0x05, 2)));
+}
@@ -0,0 +1,51 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+#[extension("I")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+#[extension("C")]
+struct CAddi();
+
+nop_execution!(Addi, CAddi);
+
+#[test]
+fn infos() {
+    let infos = GDECODER.infos();
+    assert_eq!(
+        infos.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(),
+        vec!["Addi", "CAddi"]
+    );
+    assert_eq!(
+        (
+            infos[0].format,
+            infos[0].code,
+            infos[0].mask,
+            infos[0].extension,
+            infos[0].len
+        ),
+        ("I", 0x13, 0x707f, "I", 4)
+    );
+    assert_eq!(
+        (
+            infos[1].format,
+            infos[1].code,
+            infos[1].mask,
+            infos[1].extension,
+            infos[1].len
+        ),
+        ("CI", 0x1, 0xe003, "C", 2)
+    );
+    assert!(infos.iter().all(|i| !i.file.is_empty() && i.line > 0));
+}
@@ -0,0 +1,86 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::{isa, Instruction};
+
+terminus_insn!(u32, Processor, Exception, Static);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+#[extension("I")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b????????????????0_000_00000_0010011")]
+#[priority(1)]
+#[extension("I")]
+struct AddiHint();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_111_?????_0010011")]
+#[extension("I")]
+struct Andi();
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0110011")]
+#[extension("I")]
+struct Add();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+#[extension("C")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_0_00000_00000_01")]
+#[extension("C")]
+struct CNop();
+
+nop_execution!(Addi, AddiHint, Andi, Add, CAddi, CNop);
+
+isa! { Addi, AddiHint, Andi, Add, CAddi, CNop }
+
+#[test]
+fn static_decode() {
+    assert_eq!(GDECODER.iter().count(), 6);
+    assert_eq!(GDECODER.disasm(&0x00150513), "Addi");
+    assert_eq!(GDECODER.disasm(&0x00100013), "AddiHint");
+    assert_eq!(GDECODER.disasm(&0x00157513), "Andi");
+    assert_eq!(GDECODER.disasm(&0x003100b3), "Add");
+    assert_eq!(GDECODER.disasm(&0x0505), "CAddi");
+    assert_eq!(GDECODER.disasm(&0x0001), "CNop");
+    assert_eq!(
+        GDECODER.decode(&0x00151513).err(),
+        Some(Error::Illegal(0x00151513))
+    );
+    assert_eq!(
+        GDECODER.decode(&0x403100b3).err(),
+        Some(Error::Illegal(0x403100b3))
+    );
+}
+
+#[test]
+fn static_builder() {
+    let map = DecoderBuilder::new().extensions(&["I"]).build();
+    assert_eq!(map.iter().count(), 4);
+    assert_eq!(map.disasm(&0x00100013), "AddiHint");
+    assert_eq!(
+        map.decode(&0x0505).err(),
+        Some(Error::Disabled(0x0505, "CAddi".to_string()))
+    );
+
+    let mut map = StaticInsnMap::new();
+    assert_eq!(
+        map.decode(&0x00150513).err(),
+        Some(Error::Illegal(0x00150513))
+    );
+    map.registery(Addi::decoder());
+    assert_eq!(map.disasm(&0x00100013), "Addi");
+}
@@ -0,0 +1,240 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::{isa, Instruction};
+
+terminus_insn!(u32, Processor, Exception, Static);
+
+//more instructions than the default recursion limit of the compiler, isa! must not expand them
+//one into another
+macro_rules! r_type {
+    ($($name:ident $code:tt,)*) => {
+        $(
+            #[derive(Instruction)]
+            #[format(R)]
+            #[code($code)]
+            struct $name();
+        )*
+        nop_execution!($($name),*);
+        isa! { $($name),* }
+    };
+}
+
+r_type! {
+    R00F0 "32b0000000_?????_?????_000_?????_0110011",
+    R00F1 "32b0000000_?????_?????_001_?????_0110011",
+    R00F2 "32b0000000_?????_?????_010_?????_0110011",
+    R00F3 "32b0000000_?????_?????_011_?????_0110011",
+    R00F4 "32b0000000_?????_?????_100_?????_0110011",
+    R00F5 "32b0000000_?????_?????_101_?????_0110011",
+    R00F6 "32b0000000_?????_?????_110_?????_0110011",
+    R00F7 "32b0000000_?????_?????_111_?????_0110011",
+    R01F0 "32b0000001_?????_?????_000_?????_0110011",
+    R01F1 "32b0000001_?????_?????_001_?????_0110011",
+    R01F2 "32b0000001_?????_?????_010_?????_0110011",
+    R01F3 "32b0000001_?????_?????_011_?????_0110011",
+    R01F4 "32b0000001_?????_?????_100_?????_0110011",
+    R01F5 "32b0000001_?????_?????_101_?????_0110011",
+    R01F6 "32b0000001_?????_?????_110_?????_0110011",
+    R01F7 "32b0000001_?????_?????_111_?????_0110011",
+    R02F0 "32b0000010_?????_?????_000_?????_0110011",
+    R02F1 "32b0000010_?????_?????_001_?????_0110011",
+    R02F2 "32b0000010_?????_?????_010_?????_0110011",
+    R02F3 "32b0000010_?????_?????_011_?????_0110011",
+    R02F4 "32b0000010_?????_?????_100_?????_0110011",
+    R02F5 "32b0000010_?????_?????_101_?????_0110011",
+    R02F6 "32b0000010_?????_?????_110_?????_0110011",
+    R02F7 "32b0000010_?????_?????_111_?????_0110011",
+    R03F0 "32b0000011_?????_?????_000_?????_0110011",
+    R03F1 "32b0000011_?????_?????_001_?????_0110011",
+    R03F2 "32b0000011_?????_?????_010_?????_0110011",
+    R03F3 "32b0000011_?????_?????_011_?????_0110011",
+    R03F4 "32b0000011_?????_?????_100_?????_0110011",
+    R03F5 "32b0000011_?????_?????_101_?????_0110011",
+    R03F6 "32b0000011_?????_?????_110_?????_0110011",
+    R03F7 "32b0000011_?????_?????_111_?????_0110011",
+    R04F0 "32b0000100_?????_?????_000_?????_0110011",
+    R04F1 "32b0000100_?????_?????_001_?????_0110011",
+    R04F2 "32b0000100_?????_?????_010_?????_0110011",
+    R04F3 "32b0000100_?????_?????_011_?????_0110011",
+    R04F4 "32b0000100_?????_?????_100_?????_0110011",
+    R04F5 "32b0000100_?????_?????_101_?????_0110011",
+    R04F6 "32b0000100_?????_?????_110_?????_0110011",
+    R04F7 "32b0000100_?????_?????_111_?????_0110011",
+    R05F0 "32b0000101_?????_?????_000_?????_0110011",
+    R05F1 "32b0000101_?????_?????_001_?????_0110011",
+    R05F2 "32b0000101_?????_?????_010_?????_0110011",
+    R05F3 "32b0000101_?????_?????_011_?????_0110011",
+    R05F4 "32b0000101_?????_?????_100_?????_0110011",
+    R05F5 "32b0000101_?????_?????_101_?????_0110011",
+    R05F6 "32b0000101_?????_?????_110_?????_0110011",
+    R05F7 "32b0000101_?????_?????_111_?????_0110011",
+    R06F0 "32b0000110_?????_?????_000_?????_0110011",
+    R06F1 "32b0000110_?????_?????_001_?????_0110011",
+    R06F2 "32b0000110_?????_?????_010_?????_0110011",
+    R06F3 "32b0000110_?????_?????_011_?????_0110011",
+    R06F4 "32b0000110_?????_?????_100_?????_0110011",
+    R06F5 "32b0000110_?????_?????_101_?????_0110011",
+    R06F6 "32b0000110_?????_?????_110_?????_0110011",
+    R06F7 "32b0000110_?????_?????_111_?????_0110011",
+    R07F0 "32b0000111_?????_?????_000_?????_0110011",
+    R07F1 "32b0000111_?????_?????_001_?????_0110011",
+    R07F2 "32b0000111_?????_?????_010_?????_0110011",
+    R07F3 "32b0000111_?????_?????_011_?????_0110011",
+    R07F4 "32b0000111_?????_?????_100_?????_0110011",
+    R07F5 "32b0000111_?????_?????_101_?????_0110011",
+    R07F6 "32b0000111_?????_?????_110_?????_0110011",
+    R07F7 "32b0000111_?????_?????_111_?????_0110011",
+    R08F0 "32b0001000_?????_?????_000_?????_0110011",
+    R08F1 "32b0001000_?????_?????_001_?????_0110011",
+    R08F2 "32b0001000_?????_?????_010_?????_0110011",
+    R08F3 "32b0001000_?????_?????_011_?????_0110011",
+    R08F4 "32b0001000_?????_?????_100_?????_0110011",
+    R08F5 "32b0001000_?????_?????_101_?????_0110011",
+    R08F6 "32b0001000_?????_?????_110_?????_0110011",
+    R08F7 "32b0001000_?????_?????_111_?????_0110011",
+    R09F0 "32b0001001_?????_?????_000_?????_0110011",
+    R09F1 "32b0001001_?????_?????_001_?????_0110011",
+    R09F2 "32b0001001_?????_?????_010_?????_0110011",
+    R09F3 "32b0001001_?????_?????_011_?????_0110011",
+    R09F4 "32b0001001_?????_?????_100_?????_0110011",
+    R09F5 "32b0001001_?????_?????_101_?????_0110011",
+    R09F6 "32b0001001_?????_?????_110_?????_0110011",
+    R09F7 "32b0001001_?????_?????_111_?????_0110011",
+    R10F0 "32b0001010_?????_?????_000_?????_0110011",
+    R10F1 "32b0001010_?????_?????_001_?????_0110011",
+    R10F2 "32b0001010_?????_?????_010_?????_0110011",
+    R10F3 "32b0001010_?????_?????_011_?????_0110011",
+    R10F4 "32b0001010_?????_?????_100_?????_0110011",
+    R10F5 "32b0001010_?????_?????_101_?????_0110011",
+    R10F6 "32b0001010_?????_?????_110_?????_0110011",
+    R10F7 "32b0001010_?????_?????_111_?????_0110011",
+    R11F0 "32b0001011_?????_?????_000_?????_0110011",
+    R11F1 "32b0001011_?????_?????_001_?????_0110011",
+    R11F2 "32b0001011_?????_?????_010_?????_0110011",
+    R11F3 "32b0001011_?????_?????_011_?????_0110011",
+    R11F4 "32b0001011_?????_?????_100_?????_0110011",
+    R11F5 "32b0001011_?????_?????_101_?????_0110011",
+    R11F6 "32b0001011_?????_?????_110_?????_0110011",
+    R11F7 "32b0001011_?????_?????_111_?????_0110011",
+    R12F0 "32b0001100_?????_?????_000_?????_0110011",
+    R12F1 "32b0001100_?????_?????_001_?????_0110011",
+    R12F2 "32b0001100_?????_?????_010_?????_0110011",
+    R12F3 "32b0001100_?????_?????_011_?????_0110011",
+    R12F4 "32b0001100_?????_?????_100_?????_0110011",
+    R12F5 "32b0001100_?????_?????_101_?????_0110011",
+    R12F6 "32b0001100_?????_?????_110_?????_0110011",
+    R12F7 "32b0001100_?????_?????_111_?????_0110011",
+    R13F0 "32b0001101_?????_?????_000_?????_0110011",
+    R13F1 "32b0001101_?????_?????_001_?????_0110011",
+    R13F2 "32b0001101_?????_?????_010_?????_0110011",
+    R13F3 "32b0001101_?????_?????_011_?????_0110011",
+    R13F4 "32b0001101_?????_?????_100_?????_0110011",
+    R13F5 "32b0001101_?????_?????_101_?????_0110011",
+    R13F6 "32b0001101_?????_?????_110_?????_0110011",
+    R13F7 "32b0001101_?????_?????_111_?????_0110011",
+    R14F0 "32b0001110_?????_?????_000_?????_0110011",
+    R14F1 "32b0001110_?????_?????_001_?????_0110011",
+    R14F2 "32b0001110_?????_?????_010_?????_0110011",
+    R14F3 "32b0001110_?????_?????_011_?????_0110011",
+    R14F4 "32b0001110_?????_?????_100_?????_0110011",
+    R14F5 "32b0001110_?????_?????_101_?????_0110011",
+    R14F6 "32b0001110_?????_?????_110_?????_0110011",
+    R14F7 "32b0001110_?????_?????_111_?????_0110011",
+    R15F0 "32b0001111_?????_?????_000_?????_0110011",
+    R15F1 "32b0001111_?????_?????_001_?????_0110011",
+    R15F2 "32b0001111_?????_?????_010_?????_0110011",
+    R15F3 "32b0001111_?????_?????_011_?????_0110011",
+    R15F4 "32b0001111_?????_?????_100_?????_0110011",
+    R15F5 "32b0001111_?????_?????_101_?????_0110011",
+    R15F6 "32b0001111_?????_?????_110_?????_0110011",
+    R15F7 "32b0001111_?????_?????_111_?????_0110011",
+    R16F0 "32b0010000_?????_?????_000_?????_0110011",
+    R16F1 "32b0010000_?????_?????_001_?????_0110011",
+    R16F2 "32b0010000_?????_?????_010_?????_0110011",
+    R16F3 "32b0010000_?????_?????_011_?????_0110011",
+    R16F4 "32b0010000_?????_?????_100_?????_0110011",
+    R16F5 "32b0010000_?????_?????_101_?????_0110011",
+    R16F6 "32b0010000_?????_?????_110_?????_0110011",
+    R16F7 "32b0010000_?????_?????_111_?????_0110011",
+    R17F0 "32b0010001_?????_?????_000_?????_0110011",
+    R17F1 "32b0010001_?????_?????_001_?????_0110011",
+    R17F2 "32b0010001_?????_?????_010_?????_0110011",
+    R17F3 "32b0010001_?????_?????_011_?????_0110011",
+    R17F4 "32b0010001_?????_?????_100_?????_0110011",
+    R17F5 "32b0010001_?????_?????_101_?????_0110011",
+    R17F6 "32b0010001_?????_?????_110_?????_0110011",
+    R17F7 "32b0010001_?????_?????_111_?????_0110011",
+    R18F0 "32b0010010_?????_?????_000_?????_0110011",
+    R18F1 "32b0010010_?????_?????_001_?????_0110011",
+    R18F2 "32b0010010_?????_?????_010_?????_0110011",
+    R18F3 "32b0010010_?????_?????_011_?????_0110011",
+    R18F4 "32b0010010_?????_?????_100_?????_0110011",
+    R18F5 "32b0010010_?????_?????_101_?????_0110011",
+    R18F6 "32b0010010_?????_?????_110_?????_0110011",
+    R18F7 "32b0010010_?????_?????_111_?????_0110011",
+    R19F0 "32b0010011_?????_?????_000_?????_0110011",
+    R19F1 "32b0010011_?????_?????_001_?????_0110011",
+    R19F2 "32b0010011_?????_?????_010_?????_0110011",
+    R19F3 "32b0010011_?????_?????_011_?????_0110011",
+    R19F4 "32b0010011_?????_?????_100_?????_0110011",
+    R19F5 "32b0010011_?????_?????_101_?????_0110011",
+    R19F6 "32b0010011_?????_?????_110_?????_0110011",
+    R19F7 "32b0010011_?????_?????_111_?????_0110011",
+    R20F0 "32b0010100_?????_?????_000_?????_0110011",
+    R20F1 "32b0010100_?????_?????_001_?????_0110011",
+    R20F2 "32b0010100_?????_?????_010_?????_0110011",
+    R20F3 "32b0010100_?????_?????_011_?????_0110011",
+    R20F4 "32b0010100_?????_?????_100_?????_0110011",
+    R20F5 "32b0010100_?????_?????_101_?????_0110011",
+    R20F6 "32b0010100_?????_?????_110_?????_0110011",
+    R20F7 "32b0010100_?????_?????_111_?????_0110011",
+    R21F0 "32b0010101_?????_?????_000_?????_0110011",
+    R21F1 "32b0010101_?????_?????_001_?????_0110011",
+    R21F2 "32b0010101_?????_?????_010_?????_0110011",
+    R21F3 "32b0010101_?????_?????_011_?????_0110011",
+    R21F4 "32b0010101_?????_?????_100_?????_0110011",
+    R21F5 "32b0010101_?????_?????_101_?????_0110011",
+    R21F6 "32b0010101_?????_?????_110_?????_0110011",
+    R21F7 "32b0010101_?????_?????_111_?????_0110011",
+    R22F0 "32b0010110_?????_?????_000_?????_0110011",
+    R22F1 "32b0010110_?????_?????_001_?????_0110011",
+    R22F2 "32b0010110_?????_?????_010_?????_0110011",
+    R22F3 "32b0010110_?????_?????_011_?????_0110011",
+    R22F4 "32b0010110_?????_?????_100_?????_0110011",
+    R22F5 "32b0010110_?????_?????_101_?????_0110011",
+    R22F6 "32b0010110_?????_?????_110_?????_0110011",
+    R22F7 "32b0010110_?????_?????_111_?????_0110011",
+    R23F0 "32b0010111_?????_?????_000_?????_0110011",
+    R23F1 "32b0010111_?????_?????_001_?????_0110011",
+    R23F2 "32b0010111_?????_?????_010_?????_0110011",
+    R23F3 "32b0010111_?????_?????_011_?????_0110011",
+    R23F4 "32b0010111_?????_?????_100_?????_0110011",
+    R23F5 "32b0010111_?????_?????_101_?????_0110011",
+    R23F6 "32b0010111_?????_?????_110_?????_0110011",
+    R23F7 "32b0010111_?????_?????_111_?????_0110011",
+    R24F0 "32b0011000_?????_?????_000_?????_0110011",
+    R24F1 "32b0011000_?????_?????_001_?????_0110011",
+    R24F2 "32b0011000_?????_?????_010_?????_0110011",
+    R24F3 "32b0011000_?????_?????_011_?????_0110011",
+    R24F4 "32b0011000_?????_?????_100_?????_0110011",
+    R24F5 "32b0011000_?????_?????_101_?????_0110011",
+    R24F6 "32b0011000_?????_?????_110_?????_0110011",
+    R24F7 "32b0011000_?????_?????_111_?????_0110011",
+}
+
+#[test]
+fn static_decode_many() {
+    assert_eq!(GDECODER.iter().count(), 200);
+    for funct7 in 0..25 {
+        for funct3 in 0..8 {
+            let ir = funct7 << 25 | 3 << 20 | 2 << 15 | funct3 << 12 | 1 << 7 | 0x33;
+            assert_eq!(GDECODER.disasm(&ir), format!("R{:02}F{}", funct7, funct3));
+        }
+    }
+    assert_eq!(
+        GDECODER.decode(&0x323100b3).err(),
+        Some(Error::Illegal(0x323100b3))
+    );
+}
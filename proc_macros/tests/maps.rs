@@ -0,0 +1,37 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_111_?????_0010011")]
+struct Andi();
+
+nop_execution!(Addi, Andi);
+
+#[test]
+fn tree_map_from_registry() {
+    for _ in 0..16 {
+        let mut map = TreeInsnMap::new();
+        for r in REGISTERY_INSN {
+            map.registery(r())
+        }
+        map.lock();
+        assert_eq!(map.iter().count(), 2);
+        assert!(map.decode(&0x00150513).is_ok());
+        assert!(map.decode(&0x00157513).is_ok());
+        assert_eq!(
+            map.decode(&0x00150533).err(),
+            Some(Error::Illegal(0x00150533))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0110011")]
+struct Add();
+
+#[derive(Instruction)]
+#[format(B)]
+#[code("32b?????????????????_000_?????_1100011")]
+struct Beq();
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_???_?????_1010011")]
+#[asm("fadd.s {rd:f}, {rs1:f}, {rs2:f}")]
+struct FaddS();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_001_?????_1110011")]
+#[reads("rs1, csr")]
+#[writes("rd, csr")]
+struct Csrrw();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b010_?_?????_?????_01")]
+struct CLi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b011_?_?????_?????_01")]
+struct CLui();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b010_?_?????_?????_10")]
+struct CLwsp();
+
+#[derive(Instruction)]
+#[format(CSS)]
+#[code("16b110_??????_?????_10")]
+struct CSwsp();
+
+#[derive(Instruction)]
+#[format(CR)]
+#[code("16b1000_?????_00000_10")]
+struct CJr();
+
+#[derive(Instruction)]
+#[format(CB)]
+#[code("16b110_???_???_?????_01")]
+struct CBeqz();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b001_???????????_01")]
+struct CJal();
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b101_???????????_01")]
+struct CJ();
+
+nop_execution!(Add, Beq, FaddS, Csrrw, CLi, CLui, CLwsp, CSwsp, CJr, CBeqz, CJal, CJ);
+
+fn operands(ir: u32) -> Operands {
+    GDECODER.decode(&ir).unwrap().operands(&ir)
+}
+
+#[test]
+fn default_operands() {
+    assert_eq!(
+        operands(0x00000033),
+        Operands {
+            reads: vec![Reg::X(0), Reg::X(0)],
+            writes: vec![Reg::X(0)]
+        }
+    );
+    assert_eq!(
+        operands(0x00b50463),
+        Operands {
+            reads: vec![Reg::X(10), Reg::X(11)],
+            writes: vec![]
+        }
+    );
+    assert_eq!(
+        operands(0x00c58553),
+        Operands {
+            reads: vec![Reg::F(11), Reg::F(12)],
+            writes: vec![Reg::F(10)]
+        }
+    );
+}
+
+#[test]
+fn explicit_operands() {
+    assert_eq!(
+        operands(0x30051573),
+        Operands {
+            reads: vec![Reg::X(10), Reg::Csr(0x300)],
+            writes: vec![Reg::X(10), Reg::Csr(0x300)]
+        }
+    );
+}
+
+#[test]
+fn compressed_operands() {
+    assert_eq!(
+        operands(0x4505),
+        Operands {
+            reads: vec![],
+            writes: vec![Reg::X(10)]
+        }
+    );
+    assert_eq!(
+        operands(0x6505),
+        Operands {
+            reads: vec![],
+            writes: vec![Reg::X(10)]
+        }
+    );
+    assert_eq!(
+        operands(0x4502),
+        Operands {
+            reads: vec![Reg::X(2)],
+            writes: vec![Reg::X(10)]
+        }
+    );
+    assert_eq!(
+        operands(0xc02a),
+        Operands {
+            reads: vec![Reg::X(2), Reg::X(10)],
+            writes: vec![]
+        }
+    );
+    assert_eq!(
+        operands(0x8082),
+        Operands {
+            reads: vec![Reg::X(1)],
+            writes: vec![]
+        }
+    );
+    assert_eq!(
+        operands(0xc101),
+        Operands {
+            reads: vec![Reg::X(10)],
+            writes: vec![]
+        }
+    );
+    assert_eq!(
+        operands(0x2001),
+        Operands {
+            reads: vec![],
+            writes: vec![Reg::X(1)]
+        }
+    );
+    assert_eq!(operands(0xa001), Operands::default());
+}
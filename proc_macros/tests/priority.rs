@@ -0,0 +1,57 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_0_00000_00000_01")]
+struct CNop();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b010_?_?????_?????_01")]
+#[priority(1)]
+struct CLi();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b01?_0_00000_?????_01")]
+#[reads("")]
+#[writes("")]
+struct CHint();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b011_?_0000?_????0_01")]
+struct CLuiHint();
+
+nop_execution!(CAddi, CNop, CLi, CHint, CLuiHint);
+
+#[test]
+fn precedence() {
+    assert_eq!(GDECODER.disasm(&0x0001), "CNop");
+    assert_eq!(GDECODER.disasm(&0x0505), "CAddi");
+    assert_eq!(GDECODER.disasm(&0x4001), "CLi");
+    assert_eq!(GDECODER.disasm(&0x6001), "CHint");
+    assert_eq!(GDECODER.conflicts().len(), 3);
+    assert_eq!(
+        GDECODER.ambiguities(),
+        vec![Conflict {
+            name: "CHint".to_string(),
+            code: 0x4001,
+            mask: 0xdf83,
+            other_name: "CLuiHint".to_string(),
+            other_code: 0x6001,
+            other_mask: 0xef07,
+        }]
+    );
+}
@@ -0,0 +1,47 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b00000000000000000_000_00000_0010011")]
+struct Nop();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+struct CAddi();
+
+nop_execution!(Addi, Nop, CAddi);
+
+#[test]
+fn random_insn() {
+    let mut rng = rng();
+    for _ in 0..100 {
+        let ir = GDECODER
+            .random_insn(&mut rng, |d, ir| {
+                d.name() == "Addi" && d.decode().rd(ir) != 0
+            })
+            .unwrap();
+        assert_eq!(GDECODER.decode(&ir).unwrap().name(), "Addi");
+        assert_ne!(Addi().rd(&ir), 0);
+        let ir = GDECODER
+            .random_insn(&mut rng, |d, _| d.name() == "CAddi")
+            .unwrap();
+        assert_eq!(ir >> 16, 0);
+    }
+    assert_eq!(
+        GDECODER.random_insn(&mut rng, |d, _| d.name() == "Nop"),
+        Some(0x13)
+    );
+    assert_eq!(GDECODER.random_insn(&mut rng, |_, ir| *ir == 0x33), None);
+}
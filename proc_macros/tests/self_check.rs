@@ -0,0 +1,72 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception, Simple);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+#[extension("I")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b00000000000000000_000_00000_0010011")]
+#[extension("I")]
+struct Nop();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+#[extension("C")]
+struct CAddi();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_110_?????_0010001")]
+#[extension("I")]
+struct Ori();
+
+#[derive(Instruction)]
+#[format(R)]
+#[code("32b0000000_?????_?????_000_?????_0010011")]
+#[priority(-1)]
+#[extension("I")]
+struct AddiR();
+
+#[derive(Instruction)]
+#[format(CL)]
+#[code("16b100_???_???_??_???_00")]
+#[reserved("16b100_???_???_??_???_00")]
+#[extension("Xreserved")]
+struct CReserved();
+
+nop_execution!(Addi, Nop, CAddi, Ori, AddiR, CReserved);
+
+#[test]
+fn self_check_errors() {
+    let errors = self_check(&*GDECODER, &mut rng(), 1 << 16);
+    assert_eq!(
+        errors,
+        vec![
+            CheckError::Length("Ori".to_string(), 0x6011, 4),
+            CheckError::Unreachable("CReserved".to_string()),
+            CheckError::Unreachable("AddiR".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn self_check_no_32bit_words() {
+    let errors = self_check(&*GDECODER, &mut || u64::MAX, 16);
+    for name in ["Addi", "Nop", "Ori"] {
+        assert!(errors.contains(&CheckError::Unreachable(name.to_string())));
+    }
+    //the disabled 32-bit insts are not decoded differently by the reference maps either
+    let map = DecoderBuilder::new().extensions(&["C"]).build();
+    let errors = self_check(&map, &mut || u64::MAX, 16);
+    assert!(errors.is_empty());
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "stats")]
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+struct Addi();
+
+#[derive(Instruction)]
+#[format(U)]
+#[code("32b?????????????????????????_0110111")]
+struct Lui();
+
+nop_execution!(Addi, Lui);
+
+#[test]
+fn decode_stats() {
+    for ir in [0x00150513, 0x000012b7, 0xfff50593, 0xffffffff] {
+        let _ = GDECODER.decode(&ir);
+    }
+    assert_eq!(
+        stats(),
+        vec![("Addi".to_string(), 2), ("Lui".to_string(), 1)]
+    );
+    reset_stats();
+    assert_eq!(
+        stats(),
+        vec![("Addi".to_string(), 0), ("Lui".to_string(), 0)]
+    );
+}
+
+#[test]
+fn map_stats() {
+    let map = DecoderBuilder::new().build();
+    for ir in [0x000012b7, 0x000022b7, 0x00150513] {
+        let _ = map.decode(&ir);
+    }
+    assert_eq!(
+        map.stats(),
+        vec![("Lui".to_string(), 2), ("Addi".to_string(), 1)]
+    );
+    map.reset_stats();
+    assert_eq!(
+        map.stats(),
+        vec![("Addi".to_string(), 0), ("Lui".to_string(), 0)]
+    );
+}
@@ -0,0 +1,118 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_000_?????_0010011")]
+#[asm("addi {rd}, {rs1}, {imm:d}")]
+struct Addi();
+
+impl Execution for Addi {
+    fn execute(&self, p: &mut Processor) -> Result<(), Exception> {
+        let (rd, rs1) = (self.rd(&p.ir) as usize, self.rs1(&p.ir) as usize);
+        if rd != 0 {
+            p.x[rd] = p.x[rs1].wrapping_add(self.imm_sext(&p.ir) as i32 as u64);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_010_?????_0000011")]
+struct Lw();
+
+impl Execution for Lw {
+    fn execute(&self, p: &mut Processor) -> Result<(), Exception> {
+        p.x[self.rd(&p.ir) as usize] = 0xffffffff80000000;
+        Ok(())
+    }
+}
+
+#[derive(Instruction)]
+#[format(S)]
+#[code("32b???????_?????_?????_010_?????_0100011")]
+struct Sw();
+
+#[derive(Instruction)]
+#[format(I)]
+#[code("32b?????????????????_001_?????_1110011")]
+#[reads("rs1, csr")]
+#[writes("rd, csr")]
+struct Csrrw();
+
+nop_execution!(Sw, Csrrw);
+
+#[test]
+fn spike_commit_log() {
+    let mut p = Processor::default();
+    let mut log = vec![];
+    let mut names = vec![];
+    {
+        let mut tracer = Tracer::new();
+        tracer.pre(|_, e| names.push((e.name, e.pc)));
+        tracer.post(SpikeCommitLog::new(0, 64).hook(
+            &mut log,
+            |p: &Processor, r| match r {
+                Reg::X(i) => p.x[i as usize],
+                Reg::Csr(0x300) => 0xa00000008,
+                _ => 0,
+            },
+            |p: &Processor, e| match e.name {
+                "Lw" => vec![MemAccess::Load(p.x[11])],
+                "Sw" => vec![MemAccess::Store(p.x[11] + 4, p.x[10] as u32 as u64, 4)],
+                _ => vec![],
+            },
+            |_| 3,
+        ));
+        for (pc, ir) in [
+            (0x80000000, 0x00150513),
+            (0x80000004, 0xfff50593),
+            (0x80000008, 0x30051073),
+            (0x8000000c, 0x0005a503),
+            (0x80000010, 0x00a5a223),
+        ] {
+            p.ir = ir;
+            if pc == 0x8000000c {
+                p.x[11] = 0x80001000;
+            }
+            let insn = GDECODER.decode(&ir).unwrap();
+            assert!(tracer.execute(insn, &mut p, pc, ir).is_ok());
+        }
+    }
+    assert_eq!(
+        names,
+        vec![
+            ("Addi", 0x80000000),
+            ("Addi", 0x80000004),
+            ("Csrrw", 0x80000008),
+            ("Lw", 0x8000000c),
+            ("Sw", 0x80000010)
+        ]
+    );
+    //the last three lines are as spike 1.1 logs csrw mstatus, a0; lw a0, 0(a1); sw a0, 4(a1)
+    assert_eq!(
+        String::from_utf8(log).unwrap(),
+        "\
+core   0: 3 0x0000000080000000 (0x00150513) x10 0x0000000000000001
+core   0: 3 0x0000000080000004 (0xfff50593) x11 0x0000000000000000
+core   0: 3 0x0000000080000008 (0x30051073) c768_mstatus 0x0000000a00000008
+core   0: 3 0x000000008000000c (0x0005a503) x10 0xffffffff80000000 mem 0x0000000080001000
+core   0: 3 0x0000000080000010 (0x00a5a223) mem 0x0000000080001004 0x80000000
+"
+    );
+}
+
+#[test]
+fn csr_names() {
+    assert_eq!(SpikeCommitLog::csr_name(0x300), "mstatus");
+    assert_eq!(SpikeCommitLog::csr_name(0x3a2), "pmpcfg2");
+    assert_eq!(SpikeCommitLog::csr_name(0x3bf), "pmpaddr15");
+    assert_eq!(SpikeCommitLog::csr_name(0xc1f), "hpmcounter31");
+    assert_eq!(SpikeCommitLog::csr_name(0x7ff), "unknown-csr");
+}
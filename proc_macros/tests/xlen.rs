@@ -0,0 +1,61 @@
+#[macro_use]
+mod common;
+use common::*;
+use terminus_macros::*;
+use terminus_proc_macros::Instruction;
+
+terminus_insn!(u32, Processor, Exception);
+
+#[derive(Instruction)]
+#[format(CJ)]
+#[code("16b001_???????????_01")]
+#[xlen(32)]
+struct CJal();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b001_?_?????_?????_01")]
+#[xlen(64)]
+struct CAddiw();
+
+#[derive(Instruction)]
+#[format(CI)]
+#[code("16b000_?_?????_?????_01")]
+struct CAddi();
+
+nop_execution!(CJal, CAddiw, CAddi);
+
+#[test]
+fn per_xlen_decoders() {
+    let rv32 = DecoderBuilder::new().xlen(32).build();
+    let rv64 = DecoderBuilder::new().xlen(64).build();
+    assert_eq!(rv32.disasm(&0x2505), "CJal");
+    assert_eq!(rv64.disasm(&0x2505), "CAddiw");
+    assert_eq!(rv64.disasm(&0x0505), "CAddi");
+    assert_eq!(GDECODER.iter().count(), 3);
+    assert!(rv32.ambiguities().is_empty());
+    assert!(rv64.ambiguities().is_empty());
+}
+
+#[test]
+fn xlen_ambiguities() {
+    let tie = Conflict {
+        name: "CAddiw".to_string(),
+        code: 0x2001,
+        mask: 0xe003,
+        other_name: "CJal".to_string(),
+        other_code: 0x2001,
+        other_mask: 0xe003,
+    };
+    assert_eq!(GDECODER.conflicts(), vec![tie.clone()]);
+    assert_eq!(GDECODER.ambiguities(), vec![tie]);
+    assert_eq!(
+        GDECODER.decode(&0x2505).err(),
+        Some(Error::Ambiguous(
+            0x2505,
+            "CAddiw".to_string(),
+            "CJal".to_string()
+        ))
+    );
+    assert_eq!(GDECODER.disasm(&0x0505), "CAddi");
+}